@@ -18,8 +18,10 @@ while (true)
 {
     updateCount++;
     var quote = core.GetQuote();
+    var health = core.GetFeedHealth();
+    var bookValid = core.BookValid();
     
-    if (quote.Mid > 0)
+    if (quote.Mid > 0 && health.Status == FeedStatus.Live && bookValid)
     {
         // Execute automatic trading
         var tradeResult = core.AutoTrade();
@@ -70,10 +72,20 @@ while (true)
             }
         }
     }
-    else
+    else if (health.Status == FeedStatus.Live && !bookValid)
+    {
+        var sanity = core.GetSanityCounters();
+        Console.WriteLine($"Book crossed or locked, quoting halted (crossed: {sanity.CrossedBooks}, locked: {sanity.LockedBooks})");
+    }
+    else if (health.Status == FeedStatus.Connecting || health.Status == FeedStatus.Live)
     {
         Console.WriteLine($"Connecting... ({updateCount})");
     }
+    else
+    {
+        var reason = core.FeedError();
+        Console.WriteLine($"Feed {health.Status}, quoting paused (reconnects: {health.ReconnectCount}{(reason != null ? $", last error: {reason}" : "")})");
+    }
     
     Thread.Sleep(1000);
 }
\ No newline at end of file
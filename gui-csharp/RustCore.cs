@@ -18,6 +18,136 @@ public struct Quote
     public double Pnl;
     public double UnrealizedPnl;
     public ulong LatencyUs;
+    public double BidSize;
+    public double AskSize;
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct TradeFlow
+{
+    public double BuyVolume;
+    public double SellVolume;
+    public double Imbalance;
+    public ulong TradeCount;
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct Bar
+{
+    public ulong OpenTimeNs;
+    public ulong CloseTimeNs;
+    public double Open;
+    public double High;
+    public double Low;
+    public double Close;
+    public double Volume;
+    public double DollarVolume;
+    public ulong TradeCount;
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct TopOfBook
+{
+    public double Bid;
+    public double BidSize;
+    public double Ask;
+    public double AskSize;
+    public double LastPrice;
+    public ulong Sequence;
+    public ulong ExchangeTimeNs;
+    public ulong ReceiveTimeNs;
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct ArrivalEstimate
+{
+    public double A;
+    public double K;
+    public ulong TradeCount;
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct LatencyStats
+{
+    public ulong SampleCount;
+    public long P50Ns;
+    public long P99Ns;
+    public long MinNs;
+    public long MaxNs;
+}
+
+public enum FeedStream
+{
+    Ticker = 0,
+    Depth = 1,
+    Book = 2,
+    Trade = 3,
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct SanityCounters
+{
+    public ulong InvalidPrints;
+    public ulong Outliers;
+    public ulong ZeroSizeLevels;
+    public ulong CrossedBooks;
+    public ulong LockedBooks;
+}
+
+public enum Venue
+{
+    Binance = 0,
+    Coinbase = 1,
+    Kraken = 2,
+}
+
+public enum FeedStatus
+{
+    Connecting = 0,
+    Live = 1,
+    Stale = 2,
+    Down = 3,
+}
+
+[StructLayout(LayoutKind.Sequential)]
+public struct FeedHealth
+{
+    public FeedStatus Status;
+    public ulong LastMessageAgeMs;
+    public ulong ReconnectCount;
+    public ulong ErrorCount;
+}
+
+public enum BarKind
+{
+    Time = 0,
+    Volume = 1,
+    Dollar = 2,
+}
+
+public enum VolatilityEstimator
+{
+    TimeSampled = 0,
+    Ewma = 1,
+    Parkinson = 2,
+    GarmanKlass = 3,
+}
+
+public enum QuoteModel
+{
+    AvellanedaStoikov = 0,
+    FixedSpread = 1,
+    VolatilityScaled = 2,
+    Glft = 3,
+    HjbPolicy = 4,
+}
+
+public enum FairValueModel
+{
+    LastPrice = 0,
+    Mid = 1,
+    Microprice = 2,
+    Imbalance = 3,
 }
 
 public unsafe class RustCore : IDisposable
@@ -33,6 +163,12 @@ public unsafe class RustCore : IDisposable
     [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
     private static extern Quote get_current_quote(IntPtr core);
 
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern TradeFlow get_trade_flow(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void set_trade_window(IntPtr core, double windowSecs);
+
     [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
     private static extern void destroy_core(IntPtr core);
 
@@ -48,6 +184,96 @@ public unsafe class RustCore : IDisposable
     [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
     private static extern int auto_trade(IntPtr core, byte[] result, int len);
 
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int start_venue_market_data(IntPtr core, int venue);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int start_tick_recording(IntPtr core, byte[] path);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void stop_tick_recording(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void start_synthetic_market_data(IntPtr core, double initialPrice, double volatility, ulong seed);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int start_replay(IntPtr core, byte[] path, double speed);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern long import_binance_archive(byte[] input, byte[] symbol, byte[] startDate, byte[] endDate, byte[] output);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void set_replay_speed(IntPtr core, double speed);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void set_replay_paused(IntPtr core, int paused);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void seek_replay(IntPtr core, ulong timeNs);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern ulong get_replay_position(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern TopOfBook get_top_of_book(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern FeedHealth get_feed_health(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int get_feed_error(IntPtr core, byte[] result, int len);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern ulong get_stream_age(IntPtr core, int stream);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void set_feed_stale_after(IntPtr core, double secs);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int add_bar_series(IntPtr core, int kind, double size);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int get_bars(IntPtr core, int kind, double size, [Out] Bar[] bars, int len);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_volatility_estimator(IntPtr core, int estimator, double sampleSecs, double horizonSecs, double ewmaLambda);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_quote_model(IntPtr core, int model, double param, double orderSize);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_session_horizon(IntPtr core, double lengthSecs, double closeOffsetSecs);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_arrival_intensity(IntPtr core, double a);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_fair_value(IntPtr core, int model, int levels);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_liquidity_config(IntPtr core, double bandBps, double referenceDepth);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_arrival_calibration(IntPtr core, int useForQuoting, double windowSecs, double stepBps, int levels);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern ArrivalEstimate get_arrival_estimate(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern LatencyStats get_feed_latency(IntPtr core, int stream);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int get_clock_offset(IntPtr core, out long offsetNs);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int set_sanity_filter(IntPtr core, double maxDeviationBps, int medianWindow, int rejectOutliers);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern SanityCounters get_sanity_counters(IntPtr core);
+
+    [DllImport("./libtradechest_core.so", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int is_book_valid(IntPtr core);
+
     public RustCore(string symbol)
     {
         var symbolBytes = System.Text.Encoding.UTF8.GetBytes(symbol + "\0");
@@ -55,10 +281,81 @@ public unsafe class RustCore : IDisposable
     }
 
     public void StartMarketData() => start_market_data(_core);
+    public bool StartMarketData(Venue venue) => start_venue_market_data(_core, (int)venue) == 1;
     public Quote GetQuote() => get_current_quote(_core);
+    public TradeFlow GetTradeFlow() => get_trade_flow(_core);
+    public void SetTradeWindow(double seconds) => set_trade_window(_core, seconds);
     public void SetPortfolio(double usd, double btc) => set_initial_portfolio(_core, usd, btc);
     public bool SimulateBuy(int quantity) => simulate_buy_trade(_core, quantity) == 1;
     public bool SimulateSell(int quantity) => simulate_sell_trade(_core, quantity) == 1;
+    public bool StartRecording(string path) => start_tick_recording(_core, System.Text.Encoding.UTF8.GetBytes(path + "\0")) == 1;
+    public void StopRecording() => stop_tick_recording(_core);
+    public void StartSyntheticMarketData(double initialPrice, double volatility, ulong seed) => start_synthetic_market_data(_core, initialPrice, volatility, seed);
+    public bool StartReplay(string path, double speed) => start_replay(_core, System.Text.Encoding.UTF8.GetBytes(path + "\0"), speed) == 1;
+    public static long ImportBinanceArchive(string input, string output, string symbol = null, string startDate = null, string endDate = null)
+    {
+        byte[] Utf8(string text) => text == null ? null : System.Text.Encoding.UTF8.GetBytes(text + "\0");
+        return import_binance_archive(Utf8(input), Utf8(symbol), Utf8(startDate), Utf8(endDate), Utf8(output));
+    }
+    public void SetReplaySpeed(double speed) => set_replay_speed(_core, speed);
+    public void SetReplayPaused(bool paused) => set_replay_paused(_core, paused ? 1 : 0);
+    public void SeekReplay(ulong timeNs) => seek_replay(_core, timeNs);
+    public ulong ReplayPositionNs => get_replay_position(_core);
+    public TopOfBook GetTopOfBook() => get_top_of_book(_core);
+    public FeedHealth GetFeedHealth() => get_feed_health(_core);
+    public ulong StreamAgeMs(FeedStream stream) => get_stream_age(_core, (int)stream);
+    public void SetFeedStaleAfter(double seconds) => set_feed_stale_after(_core, seconds);
+
+    public string FeedError()
+    {
+        var buffer = new byte[256];
+        return get_feed_error(_core, buffer, buffer.Length) == 1
+            ? System.Text.Encoding.UTF8.GetString(buffer).TrimEnd('\0')
+            : null;
+    }
+
+    public bool AddBarSeries(BarKind kind, double size) => add_bar_series(_core, (int)kind, size) == 1;
+
+    public Bar[] GetBars(BarKind kind, double size, int count)
+    {
+        var bars = new Bar[count];
+        var written = get_bars(_core, (int)kind, size, bars, count);
+        Array.Resize(ref bars, written);
+        return bars;
+    }
+
+    public bool SetVolatilityEstimator(VolatilityEstimator estimator, double sampleSecs, double horizonSecs, double ewmaLambda = 0.94) =>
+        set_volatility_estimator(_core, (int)estimator, sampleSecs, horizonSecs, ewmaLambda) == 1;
+
+    public bool SetQuoteModel(QuoteModel model, double param = 0, double orderSize = 1) =>
+        set_quote_model(_core, (int)model, param, orderSize) == 1;
+
+    public bool SetSessionHorizon(double lengthSecs, double closeOffsetSecs = 0) =>
+        set_session_horizon(_core, lengthSecs, closeOffsetSecs) == 1;
+
+    public bool SetArrivalIntensity(double a) => set_arrival_intensity(_core, a) == 1;
+
+    public bool SetFairValue(FairValueModel model, int levels = 5) =>
+        set_fair_value(_core, (int)model, levels) == 1;
+
+    public bool SetLiquidityConfig(double bandBps, double referenceDepth) =>
+        set_liquidity_config(_core, bandBps, referenceDepth) == 1;
+
+    public bool SetArrivalCalibration(bool useForQuoting, double windowSecs = 300, double stepBps = 0.5, int levels = 10) =>
+        set_arrival_calibration(_core, useForQuoting ? 1 : 0, windowSecs, stepBps, levels) == 1;
+
+    public ArrivalEstimate GetArrivalEstimate() => get_arrival_estimate(_core);
+
+    public LatencyStats GetFeedLatency(FeedStream stream) => get_feed_latency(_core, (int)stream);
+
+    public long? ClockOffsetNs() => get_clock_offset(_core, out var offsetNs) == 1 ? offsetNs : null;
+
+    public bool SetSanityFilter(double maxDeviationBps, int medianWindow, bool rejectOutliers = true) =>
+        set_sanity_filter(_core, maxDeviationBps, medianWindow, rejectOutliers ? 1 : 0) == 1;
+
+    public SanityCounters GetSanityCounters() => get_sanity_counters(_core);
+
+    public bool BookValid() => is_book_valid(_core) == 1;
     
     public string AutoTrade()
     {
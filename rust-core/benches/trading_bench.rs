@@ -1,9 +1,28 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::ffi::CString;
 
+#[repr(C)]
+struct Quote {
+    bid: f64,
+    ask: f64,
+    mid: f64,
+    inventory: i32,
+    market_bid: f64,
+    market_ask: f64,
+    spread: f64,
+    usd_balance: f64,
+    btc_balance: f64,
+    pnl: f64,
+    unrealized_pnl: f64,
+    latency_us: u64,
+    bid_size: f64,
+    ask_size: f64,
+}
+
 extern "C" {
     fn create_core(symbol: *const std::os::raw::c_char) -> *mut std::ffi::c_void;
-    fn get_current_quote(core: *const std::ffi::c_void) -> [f64; 12];
+    fn get_current_quote(core: *const std::ffi::c_void) -> Quote;
+    fn start_synthetic_market_data(core: *mut std::ffi::c_void, initial_price: f64, volatility: f64, seed: u64);
     fn set_initial_portfolio(core: *mut std::ffi::c_void, usd: f64, btc: f64);
     fn simulate_buy_trade(core: *mut std::ffi::c_void, quantity: i32) -> i32;
     fn simulate_sell_trade(core: *mut std::ffi::c_void, quantity: i32) -> i32;
@@ -13,6 +32,9 @@ extern "C" {
 fn benchmark_quote_generation(c: &mut Criterion) {
     let symbol = CString::new("BTCUSDT").unwrap();
     let core = unsafe { create_core(symbol.as_ptr()) };
+    // Quotes are only computed while the feed is live
+    unsafe { start_synthetic_market_data(core, 50_000.0, 0.6, 42) };
+    std::thread::sleep(std::time::Duration::from_millis(500));
     
     c.bench_function("quote_generation", |b| {
         b.iter(|| {
@@ -32,7 +54,7 @@ fn benchmark_end_to_end_latency(c: &mut Criterion) {
         b.iter(|| {
             black_box(unsafe { 
                 simulate_buy_trade(core, 1);
-                get_current_quote(core);
+                get_current_quote(core)
             })
         })
     });
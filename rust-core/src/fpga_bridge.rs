@@ -18,18 +18,23 @@ impl FPGAEngine {
         self._initialized = false;
     }
 
-    pub fn calculate_optimal_quotes(&self, mid_price: f64, inventory: i32, volatility: f64, gamma: f64, k: f64) -> (f64, f64, u32) {
+    /// Quotes for `time_to_horizon` (T - t) in years; 1.0 gives the
+    /// stationary form without horizon scaling. The software fallback
+    /// quotes with a fixed gamma of 0.1 and k of 1.5 rather than `gamma` and
+    /// `k`, except that a `fitted_k` replaces its k.
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_optimal_quotes(&self, mid_price: f64, inventory: i32, volatility: f64, gamma: f64, k: f64, fitted_k: Option<f64>, time_to_horizon: f64) -> (f64, f64, u32) {
         if !self._initialized {
-            return self.software_fallback(mid_price, inventory, volatility, 0.1, 1.5);
+            return self.software_fallback(mid_price, inventory, volatility, 0.1, fitted_k.unwrap_or(1.5), time_to_horizon);
         }
 
     // ...existing code...
         
         // FPGA disabled - use optimized software calculation
-        self.software_fallback(mid_price, inventory, volatility, gamma, k)
+        self.software_fallback(mid_price, inventory, volatility, gamma, k, time_to_horizon)
     }
 
-    fn software_fallback(&self, mid_price: f64, inventory: i32, volatility: f64, gamma: f64, k: f64) -> (f64, f64, u32) {
+    fn software_fallback(&self, mid_price: f64, inventory: i32, volatility: f64, gamma: f64, k: f64, time_to_horizon: f64) -> (f64, f64, u32) {
         // Optimized software HJB implementation
     // ...existing code...
         
@@ -37,7 +42,7 @@ impl FPGAEngine {
         
         // Vectorized calculations
     let vol_sq = volatility * volatility;
-    let gamma_vol_sq = gamma * vol_sq;
+    let gamma_vol_sq = gamma * vol_sq * time_to_horizon;
     let reservation_price = mid_price - inv_f64 * gamma_vol_sq;
 
     // Precomputed logarithm
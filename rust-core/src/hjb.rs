@@ -1,72 +1,266 @@
-// use ndarray::{Array1, Array3}; // Unused imports
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Parameters of the market-making control problem of Guéant, Lehalle and
+/// Fernandez-Tapia: a CARA trader quoting one unit on each side against
+/// fills arriving at rate `A·exp(−k·δ)` at distance `δ` from the mid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HjbParams {
+    /// Risk aversion; 0 is the risk-neutral limit.
+    pub gamma: f64,
+    /// Volatility of the mid in price units per square-root second.
+    pub sigma: f64,
+    /// Fill intensity at the mid, per second.
+    pub a: f64,
+    /// Intensity decay per unit of price distance.
+    pub k: f64,
+    /// Time to the terminal date, in seconds.
+    pub horizon: f64,
+    /// Inventory is kept within `±max_inventory` units.
+    pub max_inventory: i32,
+    /// Inventory left at the horizon costs `terminal_penalty·q²`.
+    pub terminal_penalty: f64,
+    /// Holding inventory costs `running_penalty·q²` per second, on top of
+    /// the `γσ²q²/2` implied by risk aversion.
+    pub running_penalty: f64,
+}
+
+/// Optimal bid and ask offsets from the mid on a grid of times from the
+/// start of the horizon and inventories. The side whose fill would breach
+/// the inventory bound is not quoted.
+#[derive(Debug, Clone)]
+pub struct QuotePolicy {
+    pub horizon: f64,
+    pub time_steps: usize,
+    pub max_inventory: i32,
+    /// Offsets at `time_step * inventories + (q + max_inventory)`, NaN
+    /// where the side is not quoted.
+    bid: Vec<f64>,
+    ask: Vec<f64>,
+}
+
+impl QuotePolicy {
+    fn inventories(&self) -> usize {
+        2 * self.max_inventory as usize + 1
+    }
+
+    fn index(&self, time_step: usize, inventory: i32) -> usize {
+        let q = inventory.clamp(-self.max_inventory, self.max_inventory);
+        time_step.min(self.time_steps) * self.inventories() + (q + self.max_inventory) as usize
+    }
+
+    /// Bid and ask offsets at grid time `time_step` (0 to `time_steps`) and
+    /// `inventory`, clamped to the bounds.
+    pub fn offsets(&self, time_step: usize, inventory: i32) -> (Option<f64>, Option<f64>) {
+        let index = self.index(time_step, inventory);
+        let quoted = |offset: f64| (!offset.is_nan()).then_some(offset);
+        (quoted(self.bid[index]), quoted(self.ask[index]))
+    }
+
+    /// Offsets `elapsed` seconds into the horizon, interpolated linearly
+    /// between grid times.
+    pub fn offsets_at(&self, elapsed: f64, inventory: i32) -> (Option<f64>, Option<f64>) {
+        let position = (elapsed / self.horizon * self.time_steps as f64).clamp(0.0, self.time_steps as f64);
+        let step = (position.floor() as usize).min(self.time_steps.saturating_sub(1));
+        let weight = position - step as f64;
+        let (bid_before, ask_before) = self.offsets(step, inventory);
+        let (bid_after, ask_after) = self.offsets(step + 1, inventory);
+        let lerp = |before: Option<f64>, after: Option<f64>| Some(before? + (after? - before?) * weight);
+        (lerp(bid_before, bid_after), lerp(ask_before, ask_after))
+    }
+}
 
-#[allow(dead_code)]
 pub struct HJBEngine {
-    _c: f64,
-    // Add parameters for grid size, etc. as needed
+    time_steps: usize,
 }
 
-#[allow(dead_code)]
 impl HJBEngine {
     pub fn new_default() -> Self {
-    Self { _c: 1.0 }
+        Self::new(200)
     }
 
-    /// Solve a simple HJB PDE using the Crank-Nicolson scheme for stability under high volatility.
-    /// This is a minimal illustrative implementation for u_t + a*u_xx = 0 (heat equation form).
-    pub fn solve_crank_nicolson(
-        &self,
-        x_min: f64,
-        x_max: f64,
-        t_max: f64,
-        nx: usize,
-        nt: usize,
-        volatility: f64,
-        initial: impl Fn(f64) -> f64,
-    ) -> Vec<Vec<f64>> {
-        let dx = (x_max - x_min) / (nx as f64 - 1.0);
-        let dt = t_max / (nt as f64 - 1.0);
-        let alpha = volatility * volatility * dt / (2.0 * dx * dx);
-
-        // Initialize grid
-        let mut u = vec![vec![0.0; nx]; nt];
-        for i in 0..nx {
-            let x = x_min + i as f64 * dx;
-            u[0][i] = initial(x);
-        }
+    /// Engine reporting the policy at `time_steps` intervals over the horizon.
+    pub fn new(time_steps: usize) -> Self {
+        Self { time_steps: time_steps.max(1) }
+    }
+
+    /// Solve the HJB system for the optimal quotes.
+    ///
+    /// With `θ(t, q) = ln(w_q(t)) / k`, the value function reduces to the
+    /// linear ODE system (Guéant, Lehalle and Fernandez-Tapia, 2013)
+    ///
+    /// ```text
+    /// ∂_t w_q = α·q²·w_q − η·(w_{q−1} + w_{q+1}),   w_q(T) = exp(−k·b·q²)
+    /// α = k·(γσ²/2 + φ),   η = A·(1 + γ/k)^−(1 + k/γ)
+    /// ```
+    ///
+    /// with `b` the terminal and `φ` the running penalty, and the optimal
+    /// offsets are
+    ///
+    /// ```text
+    /// δ_bid(t, q) = ln(w_q / w_{q+1}) / k + ln(1 + γ/k) / γ
+    /// δ_ask(t, q) = ln(w_q / w_{q−1}) / k + ln(1 + γ/k) / γ
+    /// ```
+    ///
+    /// The system is integrated backward from `T` with Crank-Nicolson steps,
+    /// or backward Euler steps where that is needed to keep every `w_q`
+    /// positive, renormalizing as it goes since only ratios matter.
+    pub fn solve_crank_nicolson(&self, params: &HjbParams) -> QuotePolicy {
+        self.solve_cancellable(params, &AtomicBool::new(false)).expect("solve is never cancelled")
+    }
+
+    /// `solve_crank_nicolson` that gives up and returns `None` once
+    /// `cancelled` is set, checked between time steps.
+    pub fn solve_cancellable(&self, params: &HjbParams, cancelled: &AtomicBool) -> Option<QuotePolicy> {
+        let HjbParams { gamma, sigma, a, k, horizon, max_inventory, .. } = *params;
+        let max_inventory = max_inventory.max(1);
+        let inventories = 2 * max_inventory as usize + 1;
+        let q = |i: usize| i as f64 - max_inventory as f64;
 
-        // Tridiagonal matrix coefficients
-        let a = -alpha;
-        let b = 1.0 + 2.0 * alpha;
-        let c = -alpha;
-
-        // Time stepping
-        for n in 0..nt - 1 {
-            // Right-hand side
-            let mut rhs = vec![0.0; nx];
-            for i in 1..nx - 1 {
-                rhs[i] = alpha * u[n][i - 1] + (1.0 - 2.0 * alpha) * u[n][i] + alpha * u[n][i + 1];
+        // Risk-neutral limits as γ → 0: ln(1 + γ/k)/γ → 1/k, η → A/e
+        let (base_offset, eta) = if gamma > 1e-12 {
+            ((1.0 + gamma / k).ln() / gamma, a * (1.0 + gamma / k).powf(-(1.0 + k / gamma)))
+        } else {
+            (1.0 / k, a / std::f64::consts::E)
+        };
+        let alpha = k * (0.5 * gamma * sigma * sigma + params.running_penalty);
+
+        // The implicit factor keeps w positive while it is an M-matrix,
+        // which h·η < 1/2 guarantees for any scheme weight. The explicit
+        // half of Crank-Nicolson also needs h·α·Q² <= 2, which stiff
+        // inventory penalties would only meet with tiny steps, so those
+        // steps are taken fully implicit (backward Euler) instead.
+        let dt = horizon / self.time_steps as f64;
+        let substeps = ((dt * eta / 0.25).ceil() as usize).max(1);
+        let h = dt / substeps as f64;
+        let theta = if h * alpha * (max_inventory as f64).powi(2) <= 2.0 { 0.5 } else { 1.0 };
+
+        let diagonal: Vec<f64> = (0..inventories).map(|i| alpha * q(i) * q(i)).collect();
+        let mut w: Vec<f64> = (0..inventories).map(|i| (-k * params.terminal_penalty * q(i) * q(i)).exp()).collect();
+        normalize(&mut w);
+
+        let row = |w: &[f64]| -> (Vec<f64>, Vec<f64>) {
+            let mut bid = vec![f64::NAN; inventories];
+            let mut ask = vec![f64::NAN; inventories];
+            for i in 0..inventories {
+                if i + 1 < inventories {
+                    bid[i] = (w[i] / w[i + 1]).ln() / k + base_offset;
+                }
+                if i > 0 {
+                    ask[i] = (w[i] / w[i - 1]).ln() / k + base_offset;
+                }
             }
-            // Boundary conditions (Dirichlet)
-            rhs[0] = 0.0;
-            rhs[nx - 1] = 0.0;
-
-            // Solve tridiagonal system (Thomas algorithm)
-            let mut c_star = vec![0.0; nx];
-            let mut d_star = vec![0.0; nx];
-            c_star[0] = c / b;
-            d_star[0] = rhs[0] / b;
-            for i in 1..nx {
-                let m = b - a * c_star[i - 1];
-                c_star[i] = c / m;
-                d_star[i] = (rhs[i] - a * d_star[i - 1]) / m;
+            (bid, ask)
+        };
+
+        // Rows from the terminal date back to the start
+        let mut rows = vec![row(&w)];
+        let mut rhs = vec![0.0; inventories];
+        for _ in 0..self.time_steps {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
             }
-            // Back substitution
-            u[n + 1][nx - 1] = d_star[nx - 1];
-            for i in (0..nx - 1).rev() {
-                u[n + 1][i] = d_star[i] - c_star[i] * u[n + 1][i + 1];
+            for _ in 0..substeps {
+                // (I + θh·M)·w_next = (I − (1 − θ)h·M)·w, M = diag(αq²) − η·(shift up + shift down)
+                let explicit = (1.0 - theta) * h;
+                for i in 0..inventories {
+                    let neighbours = if i > 0 { w[i - 1] } else { 0.0 } + w.get(i + 1).copied().unwrap_or(0.0);
+                    rhs[i] = (1.0 - explicit * diagonal[i]) * w[i] + explicit * eta * neighbours;
+                }
+                solve_tridiagonal(-theta * h * eta, |i| 1.0 + theta * h * diagonal[i], &rhs, &mut w);
+                normalize(&mut w);
             }
+            rows.push(row(&w));
         }
-        u
+        rows.reverse();
+
+        let (bid, ask) = rows.into_iter().fold((Vec::new(), Vec::new()), |(mut bid, mut ask), (row_bid, row_ask)| {
+            bid.extend(row_bid);
+            ask.extend(row_ask);
+            (bid, ask)
+        });
+        Some(QuotePolicy { horizon, time_steps: self.time_steps, max_inventory, bid, ask })
     }
-}
\ No newline at end of file
+}
+
+fn normalize(w: &mut [f64]) {
+    let max = w.iter().copied().fold(0.0, f64::max);
+    if max > 0.0 {
+        w.iter_mut().for_each(|value| *value /= max);
+    }
+}
+
+/// Solve a symmetric tridiagonal system with constant off-diagonal `off`
+/// and diagonal `diagonal(i)` (Thomas algorithm).
+fn solve_tridiagonal(off: f64, diagonal: impl Fn(usize) -> f64, rhs: &[f64], x: &mut [f64]) {
+    let n = rhs.len();
+    let mut c_star = vec![0.0; n];
+    let mut d_star = vec![0.0; n];
+    c_star[0] = off / diagonal(0);
+    d_star[0] = rhs[0] / diagonal(0);
+    for i in 1..n {
+        let m = diagonal(i) - off * c_star[i - 1];
+        c_star[i] = off / m;
+        d_star[i] = (rhs[i] - off * d_star[i - 1]) / m;
+    }
+    x[n - 1] = d_star[n - 1];
+    for i in (0..n - 1).rev() {
+        x[i] = d_star[i] - c_star[i] * x[i + 1];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> HjbParams {
+        HjbParams {
+            gamma: 0.05,
+            sigma: 0.3,
+            a: 1.0,
+            k: 1.5,
+            horizon: 600.0,
+            max_inventory: 5,
+            terminal_penalty: 0.0,
+            running_penalty: 0.0,
+        }
+    }
+
+    #[test]
+    fn converges_to_the_glft_closed_form_far_from_the_horizon() {
+        let p = params();
+        let policy = HJBEngine::new(100).solve_crank_nicolson(&p);
+        let base = (1.0 + p.gamma / p.k).ln() / p.gamma;
+        let omega = (p.sigma * p.sigma * p.gamma / (2.0 * p.k * p.a) * (1.0 + p.gamma / p.k).powf(1.0 + p.k / p.gamma)).sqrt();
+        // The closed form is the asymptotic approximation, accurate near zero inventory
+        for q in -1..=1 {
+            let (bid, ask) = policy.offsets(0, q);
+            let q = q as f64;
+            assert!((bid.unwrap() - (base + (2.0 * q + 1.0) / 2.0 * omega)).abs() < 0.02, "bid at q = {}", q);
+            assert!((ask.unwrap() - (base - (2.0 * q - 1.0) / 2.0 * omega)).abs() < 0.02, "ask at q = {}", q);
+        }
+    }
+
+    #[test]
+    fn terminal_quotes_price_in_the_terminal_penalty() {
+        let p = HjbParams { terminal_penalty: 0.5, ..params() };
+        let policy = HJBEngine::new(100).solve_crank_nicolson(&p);
+        let base = (1.0 + p.gamma / p.k).ln() / p.gamma;
+        for q in -4..=4 {
+            // Buying at q moves the penalty from b·q² to b·(q + 1)²
+            let (bid, ask) = policy.offsets(100, q);
+            assert!((bid.unwrap() - (base + 0.5 * (2 * q + 1) as f64)).abs() < 1e-9);
+            assert!((ask.unwrap() - (base + 0.5 * (1 - 2 * q) as f64)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn never_quotes_beyond_the_inventory_bounds() {
+        let policy = HJBEngine::new(10).solve_crank_nicolson(&params());
+        for step in 0..=10 {
+            assert_eq!(policy.offsets(step, 5).0, None);
+            assert_eq!(policy.offsets(step, -5).1, None);
+            assert!(policy.offsets(step, 4).0.is_some() && policy.offsets(step, -4).1.is_some());
+        }
+        assert_eq!(policy.offsets_at(300.0, 7), policy.offsets_at(300.0, 5));
+    }
+}
@@ -1,12 +1,22 @@
-mod hjb;
-mod market_data;
+// FFI entry points receive raw `TradingCore` pointers owned by the C# host.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+pub mod hjb;
+pub mod market_data;
 mod order_engine;
+mod policy_cache;
 mod fpga_bridge;
+pub mod quoting;
 
 // ...existing code...
-use market_data::MarketDataFeed;
+use market_data::{
+    monotonic_time_ns, parse_date_ns, ArchiveFilter, ArchiveImporter, ArrivalConfig, ArrivalEstimate, Bar, BarSpec, CoinbaseSource, FairValue, FeedHealthSnapshot, KrakenSource, LatencyStats, LiquidityConfig, SanityConfig, SanityCounters, MarketDataFeed, PriceModel, ReplaySpeed,
+    StreamKind, SyntheticConfig, SECONDS_PER_YEAR, SyntheticSource, TopOfBook, TradeFlowMetrics, VolatilityConfig, VolatilityEstimator,
+    DEFAULT_IMBALANCE_LEVELS, NANOS_PER_DAY,
+};
 use order_engine::OrderEngine;
-use fpga_bridge::FPGAEngine;
+use policy_cache::{PolicySettings, MAX_POLICY_INVENTORY};
+use quoting::{AvellanedaStoikov, FixedSpread, Glft, HjbPolicy, QuotePair, QuoteSnapshot, SessionHorizon, QuotingStrategy, VolatilityScaled};
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
@@ -14,6 +24,7 @@ use std::os::raw::c_char;
 pub struct Config {
     pub gamma: f64,  // Risk aversion
     pub k: f64,      // Liquidity cost
+    pub a: f64,      // Order arrival intensity at the mid, trades per second
     pub base_threshold: f64,
     pub max_trade_size: i32,
     pub inventory_reduction_factor: f64,
@@ -21,13 +32,22 @@ pub struct Config {
     pub vol_scalar_max: f64,
     pub time_factor_min: f64,
     pub slippage_bps: f64, // Basis points for market impact
+    pub volatility: VolatilityConfig,
+    pub fair_value: FairValue, // Reference price for quoting and P&L marking
+    pub liquidity: LiquidityConfig,
+    pub calibrate_k: bool, // Quote with the fitted k and A instead of `k` and `a` once available
+    pub session: Option<SessionHorizon>, // Scale A-S quotes by the time left in the session
 }
 
+/// Order size quoted on each side unless a model is configured with another.
+pub const DEFAULT_ORDER_SIZE: f64 = 1.0;
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             gamma: 0.1,
             k: 1.5,
+            a: 1.0,
             base_threshold: 5.0,
             max_trade_size: 5,
             inventory_reduction_factor: 0.8,
@@ -35,13 +55,32 @@ impl Default for Config {
             vol_scalar_max: 2.0,
             time_factor_min: 0.2,
             slippage_bps: 1.0, // 1 bps slippage
+            volatility: VolatilityConfig::default(),
+            fair_value: FairValue::default(),
+            liquidity: LiquidityConfig::default(),
+            calibrate_k: false,
+            session: None,
         }
     }
 }
 
+impl Config {
+    /// Inventory deviation auto-trading tolerates before rebalancing: higher
+    /// in volatile or thin markets, lower near close.
+    fn rebalance_threshold(&self, vol_scalar: f64, time_factor: f64, liquidity_factor: f64) -> i32 {
+        ((self.base_threshold * vol_scalar * time_factor.max(self.time_factor_min) / liquidity_factor) as i32).max(1)
+    }
+
+    /// Size of one rebalancing trade, a share of the deviation that shrinks
+    /// when the book is thin.
+    fn rebalance_size(&self, abs_deviation: i32, liquidity_factor: f64) -> i32 {
+        ((abs_deviation as f64 * 0.3 * liquidity_factor).ceil() as i32).clamp(1, self.max_trade_size) // Min 1, Max 5 BTC per trade
+    }
+}
+
 pub struct TradingCore {
     // ...existing code...
-    fpga_engine: FPGAEngine,
+    strategy: Box<dyn QuotingStrategy>,
     market_feed: MarketDataFeed,
     order_engine: OrderEngine,
     usd_balance: std::sync::atomic::AtomicU64,
@@ -65,6 +104,8 @@ pub struct Quote {
     pub pnl: f64,
     pub unrealized_pnl: f64,
     pub latency_us: u64,
+    pub bid_size: f64,
+    pub ask_size: f64,
 }
 
 #[no_mangle]
@@ -74,11 +115,161 @@ pub extern "C" fn create_core(symbol: *const c_char) -> *mut TradingCore {
     Box::into_raw(Box::new(core))
 }
 
+#[no_mangle]
+pub extern "C" fn destroy_core(core: *mut TradingCore) {
+    if !core.is_null() {
+        unsafe { drop(Box::from_raw(core)) };
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn start_market_data(core: *mut TradingCore) {
     unsafe { (*core).start_feed() };
 }
 
+/// Start market data from a specific exchange: 0 = Binance, 1 = Coinbase
+/// Advanced Trade, 2 = Kraken. The core's symbol must use that venue's
+/// naming (`BTCUSDT`, `BTC-USD`, `BTC/USD`). Returns 0 for an unknown venue.
+#[no_mangle]
+pub extern "C" fn start_venue_market_data(core: *mut TradingCore, venue: i32) -> i32 {
+    unsafe {
+        match venue {
+            0 => (*core).start_feed(),
+            1 => (*core).market_feed.start_with_source(Box::new(CoinbaseSource::new())),
+            2 => (*core).market_feed.start_with_source(Box::new(KrakenSource::new())),
+            _ => return 0,
+        }
+    }
+    1
+}
+
+/// Start appending all received feed events to a capture file.
+/// Returns 1 on success, 0 if the file could not be opened.
+#[no_mangle]
+pub extern "C" fn start_tick_recording(core: *mut TradingCore, path: *const c_char) -> i32 {
+    let path = unsafe { CStr::from_ptr(path).to_str().unwrap() };
+    match unsafe { (*core).market_feed.start_recording(path) } {
+        Ok(()) => 1,
+        Err(e) => {
+            log::error!("failed to start tick recording: {}", e);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn stop_tick_recording(core: *mut TradingCore) {
+    unsafe { (*core).market_feed.stop_recording() };
+}
+
+/// Drive the core from a capture file instead of the live feed. `speed` is a
+/// playback multiplier (1.0 = real time); values <= 0 replay as fast as
+/// possible. Returns 1 on success, 0 if the file is not a valid capture.
+#[no_mangle]
+pub extern "C" fn start_replay(core: *mut TradingCore, path: *const c_char, speed: f64) -> i32 {
+    let path = unsafe { CStr::from_ptr(path).to_str().unwrap() };
+    let speed = ReplaySpeed::from_factor(speed);
+    match unsafe { (*core).market_feed.start_replay(vec![path.into()], speed) } {
+        Ok(_) => 1,
+        Err(e) => {
+            log::error!("failed to start replay: {}", e);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn set_replay_speed(core: *mut TradingCore, speed: f64) {
+    if let Some(control) = unsafe { (*core).market_feed.replay_control() } {
+        control.set_speed(ReplaySpeed::from_factor(speed));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn set_replay_paused(core: *mut TradingCore, paused: i32) {
+    if let Some(control) = unsafe { (*core).market_feed.replay_control() } {
+        control.set_paused(paused != 0);
+    }
+}
+
+/// Seek the replay to a receive time in nanoseconds since the Unix epoch.
+#[no_mangle]
+pub extern "C" fn seek_replay(core: *mut TradingCore, time_ns: u64) {
+    if let Some(control) = unsafe { (*core).market_feed.replay_control() } {
+        control.seek(time_ns);
+    }
+}
+
+/// Receive time of the last replayed event, or 0 if no replay is running.
+#[no_mangle]
+pub extern "C" fn get_replay_position(core: *const TradingCore) -> u64 {
+    unsafe { (*core).market_feed.replay_control() }.map_or(0, |control| control.position_ns())
+}
+
+/// C string argument that may be null; an error if it is not UTF-8.
+unsafe fn optional_str<'a>(text: *const c_char) -> Result<Option<&'a str>, std::str::Utf8Error> {
+    if text.is_null() {
+        Ok(None)
+    } else {
+        CStr::from_ptr(text).to_str().map(Some)
+    }
+}
+
+/// Convert Binance public data archives (aggTrades, klines and bookTicker
+/// CSVs, zipped or not) under `input`, a file or directory, into a capture
+/// file at `output` for `start_replay`. `symbol`, `start_date` and
+/// `end_date` (`YYYY-MM-DD`, UTC, both inclusive) may be null to import
+/// everything. Returns the number of events written, or -1 on error,
+/// including a null `input` or `output` or an argument that is not UTF-8.
+#[no_mangle]
+pub extern "C" fn import_binance_archive(
+    input: *const c_char,
+    symbol: *const c_char,
+    start_date: *const c_char,
+    end_date: *const c_char,
+    output: *const c_char,
+) -> i64 {
+    let arguments = [input, symbol, start_date, end_date, output].map(|text| unsafe { optional_str(text) });
+    let [Ok(Some(input)), Ok(symbol), Ok(start_date), Ok(end_date), Ok(Some(output))] = arguments else {
+        log::error!("archive import needs input and output paths, and every argument must be UTF-8");
+        return -1;
+    };
+
+    let mut filter = ArchiveFilter { symbols: symbol.into_iter().map(str::to_string).collect(), ..ArchiveFilter::default() };
+    for (date, bound, offset_ns) in [(start_date, &mut filter.start_ns, 0), (end_date, &mut filter.end_ns, NANOS_PER_DAY)] {
+        if let Some(date) = date {
+            match parse_date_ns(date) {
+                Some(time_ns) => *bound = Some(time_ns + offset_ns),
+                None => {
+                    log::error!("invalid archive import date: {}", date);
+                    return -1;
+                }
+            }
+        }
+    }
+
+    match ArchiveImporter::new(&[input.into()], filter).and_then(|mut importer| importer.write_capture(output)) {
+        Ok(written) => written as i64,
+        Err(e) => {
+            log::error!("failed to import archive: {}", e);
+            -1
+        }
+    }
+}
+
+/// Drive the core from a seeded geometric Brownian motion instead of the
+/// live feed. `volatility` is annualized.
+#[no_mangle]
+pub extern "C" fn start_synthetic_market_data(core: *mut TradingCore, initial_price: f64, volatility: f64, seed: u64) {
+    let config = SyntheticConfig {
+        model: PriceModel::Gbm { drift: 0.0, volatility },
+        initial_price,
+        seed,
+        ..SyntheticConfig::default()
+    };
+    unsafe { (*core).market_feed.start_with_source(Box::new(SyntheticSource::new(config))) };
+}
+
 #[no_mangle]
 pub extern "C" fn get_current_quote(core: *const TradingCore) -> Quote {
     let start = std::time::Instant::now();
@@ -87,6 +278,206 @@ pub extern "C" fn get_current_quote(core: *const TradingCore) -> Quote {
     Quote { latency_us: latency, ..quote }
 }
 
+/// Best bid/ask, sizes and last price as of one feed update.
+#[no_mangle]
+pub extern "C" fn get_top_of_book(core: *const TradingCore) -> TopOfBook {
+    unsafe { (*core).market_feed.top_of_book() }
+}
+
+/// Connection state, last-message age, reconnects and error count of the
+/// market data stream.
+#[no_mangle]
+pub extern "C" fn get_feed_health(core: *const TradingCore) -> FeedHealthSnapshot {
+    unsafe { (*core).market_feed.health().snapshot() }
+}
+
+/// Copy the last feed error into `result` as a NUL-terminated string.
+/// Returns 1 if there was an error to report.
+#[no_mangle]
+pub extern "C" fn get_feed_error(core: *const TradingCore, result: *mut u8, len: i32) -> i32 {
+    let error = match unsafe { (*core).market_feed.health().last_error() } {
+        Some(error) if !result.is_null() && len > 0 => error,
+        _ => return 0,
+    };
+    let copy_len = std::cmp::min(error.len(), len as usize - 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(error.as_ptr(), result, copy_len);
+        *result.add(copy_len) = 0;
+    }
+    1
+}
+
+/// Milliseconds since the last message on one stream: 0 = ticker, 1 =
+/// depth, 2 = order book, 3 = trades. `u64::MAX` if the stream has had no
+/// message yet or is unknown.
+#[no_mangle]
+pub extern "C" fn get_stream_age(core: *const TradingCore, stream: i32) -> u64 {
+    let age = StreamKind::from_index(stream).and_then(|stream| unsafe { (*core).market_feed.health().stream_age(stream) });
+    age.map_or(u64::MAX, |age| age.as_millis() as u64)
+}
+
+/// Treat the feed as stale after `secs` seconds without a depth or order
+/// book update.
+#[no_mangle]
+pub extern "C" fn set_feed_stale_after(core: *mut TradingCore, secs: f64) {
+    if let Ok(stale_after) = std::time::Duration::try_from_secs_f64(secs) {
+        unsafe { (*core).market_feed.health().set_stale_after(stale_after) };
+    }
+}
+
+/// Receive minus exchange time percentiles for one stream: 0 = ticker,
+/// 1 = depth, 2 = order book, 3 = trades. All zero for an unknown stream.
+#[no_mangle]
+pub extern "C" fn get_feed_latency(core: *mut TradingCore, stream: i32) -> LatencyStats {
+    match StreamKind::from_index(stream) {
+        Some(stream) => unsafe { (*core).market_feed.latency(stream) },
+        None => LatencyStats::default(),
+    }
+}
+
+/// Write the estimated local minus exchange clock offset (ns) to `offset_ns`.
+/// Returns 0 if no exchange-timestamped event has arrived yet.
+#[no_mangle]
+pub extern "C" fn get_clock_offset(core: *mut TradingCore, offset_ns: *mut i64) -> i32 {
+    match unsafe { (*core).market_feed.clock_offset_ns() } {
+        Some(offset) => {
+            unsafe { *offset_ns = offset };
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Configure the market data sanity filters: prints further than
+/// `max_deviation_bps` from the median of the last `median_window` prints
+/// are outliers, dropped if `reject_outliers` is non-zero and only counted
+/// otherwise. Returns 0 if the deviation or window is not positive.
+#[no_mangle]
+pub extern "C" fn set_sanity_filter(
+    core: *mut TradingCore,
+    max_deviation_bps: f64,
+    median_window: i32,
+    reject_outliers: i32,
+) -> i32 {
+    if max_deviation_bps.is_nan() || max_deviation_bps <= 0.0 || median_window <= 0 {
+        return 0;
+    }
+    let config = SanityConfig {
+        max_deviation_bps,
+        median_window: median_window as usize,
+        reject_outliers: reject_outliers != 0,
+        ..SanityConfig::default()
+    };
+    unsafe { (*core).market_feed.set_sanity_config(config) };
+    1
+}
+
+/// Counts of prints and book updates rejected or flagged by the filters.
+#[no_mangle]
+pub extern "C" fn get_sanity_counters(core: *mut TradingCore) -> SanityCounters {
+    unsafe { (*core).market_feed.sanity_counters() }
+}
+
+/// 1 if the book is usable for quoting, 0 while it is crossed or locked or
+/// waiting for an order book snapshot.
+#[no_mangle]
+pub extern "C" fn is_book_valid(core: *mut TradingCore) -> i32 {
+    unsafe { (*core).market_feed.book_valid() as i32 }
+}
+
+/// Signed order-flow statistics over the rolling trade window.
+#[no_mangle]
+pub extern "C" fn get_trade_flow(core: *const TradingCore) -> TradeFlowMetrics {
+    unsafe { (*core).market_feed.trade_flow() }
+}
+
+#[no_mangle]
+pub extern "C" fn set_trade_window(core: *mut TradingCore, window_secs: f64) {
+    if let Ok(window) = std::time::Duration::try_from_secs_f64(window_secs) {
+        unsafe { (*core).market_feed.set_trade_window(window) };
+    }
+}
+
+/// Fit order arrival over the last `window_secs` of trades on a grid of
+/// `levels` distances `step_bps` apart, and quote with the fitted k and A
+/// instead of the configured ones when `use_for_quoting` is non-zero.
+/// Returns 0 if the window, step or level count is invalid.
+#[no_mangle]
+pub extern "C" fn set_arrival_calibration(
+    core: *mut TradingCore,
+    use_for_quoting: i32,
+    window_secs: f64,
+    step_bps: f64,
+    levels: i32,
+) -> i32 {
+    let window = match std::time::Duration::try_from_secs_f64(window_secs) {
+        Ok(window) if !window.is_zero() && step_bps > 0.0 && levels >= 2 => window,
+        _ => return 0,
+    };
+    unsafe {
+        let config = ArrivalConfig { window, step_bps, levels: levels as usize, ..ArrivalConfig::default() };
+        (*core).market_feed.set_arrival_config(config);
+        (*core).config.calibrate_k = use_for_quoting != 0;
+        (*core).prepare_strategy();
+    }
+    1
+}
+
+/// Set the order arrival intensity at the mid, in trades per second, used
+/// by the GLFT and HJB models while no fitted value is in use. Returns 0
+/// unless positive.
+#[no_mangle]
+pub extern "C" fn set_arrival_intensity(core: *mut TradingCore, a: f64) -> i32 {
+    if a.is_nan() || a <= 0.0 {
+        return 0;
+    }
+    unsafe {
+        (*core).config.a = a;
+        (*core).prepare_strategy();
+    }
+    1
+}
+
+/// Latest fitted order arrival parameters; all zero until enough trades
+/// have been seen.
+#[no_mangle]
+pub extern "C" fn get_arrival_estimate(core: *mut TradingCore) -> ArrivalEstimate {
+    unsafe { (*core).market_feed.arrival_estimate() }.unwrap_or_default()
+}
+
+/// Bar series selector shared by the bar FFI calls: `kind` 0 = time bars of
+/// `size` seconds, 1 = volume bars of `size` base units, 2 = dollar bars of
+/// `size` quote notional.
+fn bar_spec(kind: i32, size: f64) -> Option<BarSpec> {
+    match kind {
+        0 => std::time::Duration::try_from_secs_f64(size).ok().map(BarSpec::Time),
+        1 => Some(BarSpec::Volume(size)),
+        2 => Some(BarSpec::Dollar(size)),
+        _ => None,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn add_bar_series(core: *mut TradingCore, kind: i32, size: f64) -> i32 {
+    match bar_spec(kind, size) {
+        Some(spec) if unsafe { (*core).market_feed.add_bar_series(spec) } => 1,
+        _ => 0,
+    }
+}
+
+/// Copy up to `len` most recent bars of a series into `bars`, oldest first
+/// and ending with the bar still being built. Returns the number written.
+#[no_mangle]
+pub extern "C" fn get_bars(core: *mut TradingCore, kind: i32, size: f64, bars: *mut Bar, len: i32) -> i32 {
+    let spec = match bar_spec(kind, size) {
+        Some(spec) if !bars.is_null() && len > 0 => spec,
+        _ => return 0,
+    };
+    let latest = unsafe { (*core).market_feed.bars(spec, len as usize) };
+    unsafe { std::ptr::copy_nonoverlapping(latest.as_ptr(), bars, latest.len()) };
+    latest.len() as i32
+}
+
 #[no_mangle]
 pub extern "C" fn set_initial_portfolio(core: *mut TradingCore, usd: f64, btc: f64) {
     unsafe { (*core).set_portfolio(usd, btc) };
@@ -125,28 +516,31 @@ pub extern "C" fn simulate_sell_trade(core: *mut TradingCore, quantity: i32) ->
 #[no_mangle]
 pub extern "C" fn auto_trade(core: *mut TradingCore, result: *mut u8, len: i32) -> i32 {
     unsafe {
+        // Never trade on a dead or stale feed, or a crossed book
+        if !(*core).market_feed.health().is_live() || !(*core).market_feed.book_valid() {
+            return 0;
+        }
         let inventory = (*core).order_engine.inventory();
         let mid_price = (*core).market_feed.current_price();
-        let volatility = (*core).market_feed.realized_volatility();
+        let volatility = (*core).market_feed.realized_volatility(&(*core).config.volatility);
         
                 // Professional dynamic inventory management
         let time_factor = (*core).time_to_close_factor(); // 1.0 at open, 0.0 at close
         let vol_scalar = (volatility / 0.3).clamp((*core).config.vol_scalar_min, (*core).config.vol_scalar_max); // Scale vs base 30% vol
-        let _liquidity_factor = (*core).market_feed.liquidity_factor();
+        let liquidity_factor = (*core).market_feed.liquidity_factor(&(*core).config.liquidity);
         
         // Dynamic target: reduce inventory as market close approaches
         let base_target = (*core).initial_btc as i32;
         let target_inventory = (base_target as f64 * time_factor * (*core).config.inventory_reduction_factor) as i32; // Max 80% of base
         
-        // Dynamic threshold: higher in volatile markets, lower near close
-        let rebalance_threshold = (((*core).config.base_threshold * vol_scalar * time_factor.max((*core).config.time_factor_min)) as i32).max(1);
+        // Dynamic threshold: higher in volatile or thin markets, lower near close
+        let rebalance_threshold = (*core).config.rebalance_threshold(vol_scalar, time_factor, liquidity_factor);
         
         let deviation = inventory - target_inventory;
         let abs_deviation = deviation.abs();
         
-        // Risk-based position sizing
-        let max_trade_size = (abs_deviation as f64 * 0.3).ceil() as i32;
-        let trade_size = max_trade_size.clamp(1, (*core).config.max_trade_size); // Min 1, Max 5 BTC per trade
+        // Risk-based position sizing, smaller when the book is thin
+        let trade_size = (*core).config.rebalance_size(abs_deviation, liquidity_factor);
         
         if abs_deviation > rebalance_threshold {
             let (action, success) = if deviation > 0 {
@@ -171,6 +565,9 @@ pub extern "C" fn auto_trade(core: *mut TradingCore, result: *mut u8, len: i32)
     }
 }
 
+/// Set risk aversion `gamma` and order arrival decay `k` for the GLFT and
+/// HJB models, and the auto-trader thresholds. The Avellaneda-Stoikov
+/// model's software path keeps its fixed gamma of 0.1 and k of 1.5.
 #[no_mangle]
 pub extern "C" fn set_config(core: *mut TradingCore, gamma: f64, k: f64, base_threshold: f64, max_trade_size: i32, slippage_bps: f64) {
     unsafe {
@@ -179,14 +576,136 @@ pub extern "C" fn set_config(core: *mut TradingCore, gamma: f64, k: f64, base_th
         (*core).config.base_threshold = base_threshold;
         (*core).config.max_trade_size = max_trade_size;
         (*core).config.slippage_bps = slippage_bps;
+        (*core).prepare_strategy();
+    }
+}
+
+/// Select the realized volatility estimator: 0 = time-sampled returns,
+/// 1 = EWMA (uses `ewma_lambda`), 2 = Parkinson, 3 = Garman-Klass.
+/// Returns 0 if the estimator or durations are invalid.
+#[no_mangle]
+pub extern "C" fn set_volatility_estimator(
+    core: *mut TradingCore,
+    estimator: i32,
+    sample_secs: f64,
+    horizon_secs: f64,
+    ewma_lambda: f64,
+) -> i32 {
+    let estimator = match estimator {
+        0 => VolatilityEstimator::TimeSampled,
+        1 if ewma_lambda > 0.0 && ewma_lambda < 1.0 => VolatilityEstimator::Ewma { lambda: ewma_lambda },
+        2 => VolatilityEstimator::Parkinson,
+        3 => VolatilityEstimator::GarmanKlass,
+        _ => return 0,
+    };
+    let durations = (
+        std::time::Duration::try_from_secs_f64(sample_secs),
+        std::time::Duration::try_from_secs_f64(horizon_secs),
+    );
+    match durations {
+        (Ok(sample_interval), Ok(horizon)) if !sample_interval.is_zero() => {
+            let volatility = unsafe { &mut (*core).config.volatility };
+            volatility.estimator = estimator;
+            volatility.sample_interval = sample_interval;
+            volatility.horizon = horizon;
+            unsafe { (*core).prepare_strategy() };
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Measure liquidity as the depth within `band_bps` of the mid relative to
+/// `reference_depth` per side. Returns 0 if either is not positive.
+#[no_mangle]
+pub extern "C" fn set_liquidity_config(core: *mut TradingCore, band_bps: f64, reference_depth: f64) -> i32 {
+    if !(band_bps > 0.0 && reference_depth > 0.0) {
+        return 0;
+    }
+    let liquidity = unsafe { &mut (*core).config.liquidity };
+    liquidity.band_bps = band_bps;
+    liquidity.reference_depth = reference_depth;
+    1
+}
+
+/// Select the reference price used for quoting and P&L marking: 0 = last
+/// trade (the default), 1 = mid, 2 = microprice, 3 = imbalance over the top
+/// `levels` of each side (5 if `levels` <= 0). Returns 0 if the model is
+/// unknown.
+#[no_mangle]
+pub extern "C" fn set_fair_value(core: *mut TradingCore, model: i32, levels: i32) -> i32 {
+    let model = match model {
+        0 => FairValue::LastPrice,
+        1 => FairValue::Mid,
+        2 => FairValue::Microprice,
+        3 if levels > 0 => FairValue::Imbalance { levels: levels as usize },
+        3 => FairValue::Imbalance { levels: DEFAULT_IMBALANCE_LEVELS },
+        _ => return 0,
+    };
+    unsafe {
+        (*core).config.fair_value = model;
+        (*core).prepare_strategy();
+    }
+    1
+}
+
+/// Scale Avellaneda-Stoikov quotes by the time left in a session that
+/// closes every `length_secs`, `close_offset_secs` past each multiple of it
+/// since the Unix epoch (86400 and 0 close daily at midnight UTC). The
+/// auto-trader unwinds toward the same close. A `length_secs` of 0 disables
+/// the horizon. Returns 0 if either value is negative or not finite.
+#[no_mangle]
+pub extern "C" fn set_session_horizon(core: *mut TradingCore, length_secs: f64, close_offset_secs: f64) -> i32 {
+    let durations = (
+        std::time::Duration::try_from_secs_f64(length_secs),
+        std::time::Duration::try_from_secs_f64(close_offset_secs),
+    );
+    let session = match durations {
+        (Ok(length), _) if length.is_zero() => None,
+        (Ok(length), Ok(close_offset)) => Some(SessionHorizon { length, close_offset }),
+        _ => return 0,
+    };
+    unsafe {
+        (*core).config.session = session;
+        (*core).prepare_strategy();
     }
+    1
+}
+
+/// Select the quote model: 0 = Avellaneda-Stoikov, 1 = fixed spread of
+/// `param` bps around the fair value, 2 = symmetric half spread of `param`
+/// standard deviations of the one-minute move, 3 = GLFT with inventory
+/// bounded by `param`, 4 = precomputed HJB policy over the session horizon
+/// with inventory bounded by `param` (at most 100). Each side is quoted for
+/// `order_size` (1 if <= 0). Returns 0 if the model is unknown or its
+/// parameter is not positive.
+#[no_mangle]
+pub extern "C" fn set_quote_model(core: *mut TradingCore, model: i32, param: f64, order_size: f64) -> i32 {
+    let order_size = if order_size > 0.0 { order_size } else { DEFAULT_ORDER_SIZE };
+    let strategy: Box<dyn QuotingStrategy> = match model {
+        0 => Box::new(AvellanedaStoikov::new(order_size)),
+        1 if param > 0.0 => Box::new(FixedSpread { spread_bps: param, order_size }),
+        2 if param > 0.0 => Box::new(VolatilityScaled::new(param, order_size)),
+        3 if param >= 1.0 => Box::new(Glft { max_inventory: param as i32, order_size }),
+        4 if (1.0..=MAX_POLICY_INVENTORY as f64).contains(&param) => {
+            let settings = PolicySettings { max_inventory: param as i32, ..PolicySettings::default() };
+            Box::new(HjbPolicy::new(settings, order_size))
+        }
+        _ => return 0,
+    };
+    log::info!("quoting with the {} model", strategy.name());
+    unsafe {
+        (*core).strategy = strategy;
+        (*core).prepare_strategy();
+    }
+    1
 }
 
 impl TradingCore {
     fn new(symbol: String) -> Self {
         Self {
             // ...existing code...
-            fpga_engine: FPGAEngine::new(),
+            strategy: Box::new(AvellanedaStoikov::new(DEFAULT_ORDER_SIZE)),
             market_feed: MarketDataFeed::new(symbol),
             order_engine: OrderEngine::new(),
             usd_balance: std::sync::atomic::AtomicU64::new(0),
@@ -209,27 +728,58 @@ impl TradingCore {
         self.market_feed.start();
     }
 
+    /// Current time on the feed's clock: the receive time of the latest
+    /// update, which follows the recorded clock during replays.
+    fn market_time_ns(&self, top: &TopOfBook) -> u64 {
+        if top.receive_time_ns > 0 { top.receive_time_ns } else { monotonic_time_ns() }
+    }
+
     fn time_to_close_factor(&self) -> f64 {
-        // Simulate trading day: 24h cycle, reduce inventory toward close
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let seconds_in_day = 86400;
-        let day_progress = (now % seconds_in_day) as f64 / seconds_in_day as f64;
-        
+        // Reduce inventory toward the session close, daily at midnight UTC by default
+        let session = self.config.session.unwrap_or_default();
+        let day_progress = session.progress(self.market_time_ns(&self.market_feed.top_of_book()));
+
         // Higher factor early in day, lower near close
         if day_progress < 0.75 { 1.0 } else { (1.0 - day_progress) * 4.0 }
     }
 
+    /// Market state and configuration the quote model works from.
+    fn quote_snapshot(&self, top: &TopOfBook) -> QuoteSnapshot {
+        let (k, a, fitted) = match self.market_feed.arrival_estimate() {
+            Some(estimate) if self.config.calibrate_k => (estimate.k, estimate.a, true),
+            _ => (self.config.k, self.config.a, false),
+        };
+        QuoteSnapshot {
+            mid: self.market_feed.fair_value(self.config.fair_value, top),
+            market_bid: top.bid,
+            market_ask: top.ask,
+            inventory: self.order_engine.inventory(),
+            volatility: self.market_feed.realized_volatility(&self.config.volatility),
+            gamma: self.config.gamma,
+            k,
+            a,
+            fitted,
+            time_to_horizon: self.config.session.map_or(1.0, |session| session.time_to_horizon(self.market_time_ns(top))),
+            session_length: self.config.session.map_or(0.0, |session| session.length.as_secs_f64() / SECONDS_PER_YEAR),
+        }
+    }
+
+    /// Let the quote model precompute for the current configuration.
+    fn prepare_strategy(&self) {
+        self.strategy.prepare(&self.quote_snapshot(&self.market_feed.top_of_book()));
+    }
+
     fn get_quote(&self) -> Quote {
-        let mid_price = self.market_feed.current_price();
-        let market_bid = self.market_feed.current_bid();
-        let market_ask = self.market_feed.current_ask();
-        let inventory = self.order_engine.inventory();
-        // Use FPGA for optimal quote calculation
-        let volatility = self.market_feed.realized_volatility();
-        let (optimal_bid, optimal_ask, fpga_latency_ns) = self.fpga_engine.calculate_optimal_quotes(mid_price, inventory, volatility, self.config.gamma, self.config.k);
+        let top = self.market_feed.top_of_book();
+        let (market_bid, market_ask) = (top.bid, top.ask);
+        let snapshot = self.quote_snapshot(&top);
+        let (mid_price, inventory) = (snapshot.mid, snapshot.inventory);
+        // No quotes (0.0) off a dead or stale feed or an invalid book
+        let quotes = if self.market_feed.health().is_live() && self.market_feed.book_valid() {
+            self.strategy.quote(&snapshot)
+        } else {
+            QuotePair::default()
+        };
         
         let current_usd = f64::from_bits(self.usd_balance.load(std::sync::atomic::Ordering::Relaxed));
         let current_btc = f64::from_bits(self.btc_balance.load(std::sync::atomic::Ordering::Relaxed));
@@ -237,8 +787,8 @@ impl TradingCore {
         let realized_pnl = (current_usd - self.initial_usd) + (current_btc - self.initial_btc) * mid_price;
         
         Quote {
-            bid: optimal_bid,
-            ask: optimal_ask,
+            bid: quotes.bid,
+            ask: quotes.ask,
             mid: mid_price,
             inventory,
             market_bid,
@@ -248,7 +798,30 @@ impl TradingCore {
             btc_balance: current_btc,
             pnl: realized_pnl,
             unrealized_pnl: 0.0, // Placeholder
-            latency_us: (fpga_latency_ns / 1000) as u64, // Convert ns to μs
+            latency_us: 0, // Measured by get_current_quote
+            bid_size: quotes.bid_size,
+            ask_size: quotes.ask_size,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thin_books_raise_the_rebalance_threshold_and_shrink_trades() {
+        let config = Config::default();
+        // Base threshold 5 at normal volatility and liquidity
+        assert_eq!(config.rebalance_threshold(1.0, 1.0, 1.0), 5);
+        assert_eq!(config.rebalance_threshold(1.0, 1.0, 0.5), 10);
+        assert_eq!(config.rebalance_threshold(1.0, 1.0, 2.0), 2);
+        // The time factor is floored at `time_factor_min` before scaling
+        assert_eq!(config.rebalance_threshold(1.0, 0.0, 0.5), 2);
+
+        assert_eq!(config.rebalance_size(10, 1.0), 3);
+        assert_eq!(config.rebalance_size(10, 0.5), 2);
+        assert_eq!(config.rebalance_size(10, 2.0), 5);
+        assert_eq!(config.rebalance_size(100, 2.0), config.max_trade_size);
+        assert_eq!(config.rebalance_size(1, 0.5), 1);
+    }
+}
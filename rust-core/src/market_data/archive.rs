@@ -0,0 +1,622 @@
+//! Importer for Binance public data archives (data.binance.vision).
+//!
+//! Archive files keep the names Binance publishes them under, which carry
+//! the symbol, data type and period:
+//!
+//! ```text
+//! BTCUSDT-aggTrades-2024-01-15.zip     aggregated trades, one day
+//! BTCUSDT-1m-2024-01.zip               1-minute klines, one month
+//! BTCUSDT-bookTicker-2024-01-15.csv    best bid and ask updates
+//! ```
+//!
+//! Files may be zipped or already extracted, with or without a header row.
+//! Rows become the events a live feed would have delivered: aggregated
+//! trades become `Trade`s, book ticker updates become one-level `Depth`
+//! updates, and each kline becomes four `Ticker` prints (open, the two
+//! extremes in the order the bar direction suggests, then close). Events
+//! are stamped with the archive time as both exchange and receive time, so
+//! replays are paced by the historical clock.
+
+use super::capture;
+use super::source::{days_from_civil, FeedError, MarketEvent, Side, TimedEvent};
+use flate2::read::DeflateDecoder;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zip::{CompressionMethod, ZipArchive};
+
+pub const NANOS_PER_DAY: u64 = 86_400 * 1_000_000_000;
+
+/// Data type of an archive file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    AggTrades,
+    BookTicker,
+    Klines,
+}
+
+/// Which events to import. Times are nanoseconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArchiveFilter {
+    /// Symbols to import (case-insensitive); empty imports every symbol.
+    pub symbols: Vec<String>,
+    /// Events before this time are skipped.
+    pub start_ns: Option<u64>,
+    /// Events at or after this time are skipped.
+    pub end_ns: Option<u64>,
+}
+
+impl ArchiveFilter {
+    fn includes_symbol(&self, symbol: &str) -> bool {
+        self.symbols.is_empty() || self.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol))
+    }
+
+    fn overlaps(&self, start_ns: u64, end_ns: u64) -> bool {
+        self.start_ns.is_none_or(|start| end_ns > start) && self.end_ns.is_none_or(|end| start_ns < end)
+    }
+
+    fn includes_time(&self, time_ns: u64) -> bool {
+        self.overlaps(time_ns, time_ns + 1)
+    }
+}
+
+/// An archive file and what its name says it holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveFile {
+    pub path: PathBuf,
+    pub symbol: String,
+    pub kind: ArchiveKind,
+    /// Data type as named in the file: `aggTrades`, `bookTicker` or the
+    /// kline interval such as `1m`.
+    pub interval: String,
+    /// Start and end of the day or month the file covers.
+    pub start_ns: u64,
+    pub end_ns: u64,
+}
+
+impl ArchiveFile {
+    /// Recognize a Binance archive from its file name, `None` for anything
+    /// else (checksums, capture files, unrelated CSVs).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let stem = name.strip_suffix(".zip").or_else(|| name.strip_suffix(".csv"))?;
+        let parts: Vec<&str> = stem.split('-').collect();
+        let number = |index: usize| parts.get(index)?.parse::<i64>().ok();
+
+        let (year, month) = (number(2)?, number(3)?);
+        let (start_ns, end_ns) = match parts.len() {
+            4 => {
+                let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+                (date_ns(year, month, 1)?, date_ns(next_year, next_month, 1)?)
+            }
+            5 => {
+                let start_ns = date_ns(year, month, number(4)?)?;
+                (start_ns, start_ns + NANOS_PER_DAY)
+            }
+            _ => return None,
+        };
+        let kind = match parts[1] {
+            "aggTrades" => ArchiveKind::AggTrades,
+            "bookTicker" => ArchiveKind::BookTicker,
+            interval if is_kline_interval(interval) => ArchiveKind::Klines,
+            _ => return None,
+        };
+        Some(Self {
+            path: path.to_path_buf(),
+            symbol: parts[0].to_uppercase(),
+            kind,
+            interval: parts[1].to_string(),
+            start_ns,
+            end_ns,
+        })
+    }
+
+    /// Open the CSV, decompressing the first CSV entry of a zip on the fly.
+    fn open(&self) -> Result<Box<dyn BufRead + Send>, FeedError> {
+        let archive_err = |e: &dyn std::fmt::Display| FeedError::Archive(format!("{}: {}", self.path.display(), e));
+        let file = File::open(&self.path).map_err(|e| archive_err(&e))?;
+        if self.path.extension().is_some_and(|ext| ext == "csv") {
+            return Ok(Box::new(BufReader::new(file)));
+        }
+
+        let mut zip = ZipArchive::new(file).map_err(|e| archive_err(&e))?;
+        let mut csv_entry = None;
+        for index in 0..zip.len() {
+            let entry = zip.by_index_raw(index).map_err(|e| archive_err(&e))?;
+            if entry.name().ends_with(".csv") {
+                csv_entry = Some((entry.data_start(), entry.compressed_size(), entry.compression()));
+                break;
+            }
+        }
+        let (data_start, size, method) = csv_entry.ok_or_else(|| archive_err(&"no CSV entry in archive"))?;
+        // Read the entry straight from the file so the reader owns it
+        let mut file = zip.into_inner();
+        file.seek(SeekFrom::Start(data_start)).map_err(|e| archive_err(&e))?;
+        let data = file.take(size);
+        match method {
+            CompressionMethod::Stored => Ok(Box::new(BufReader::new(data))),
+            CompressionMethod::Deflated => Ok(Box::new(BufReader::new(DeflateDecoder::new(data)))),
+            other => Err(archive_err(&format!("unsupported compression {}", other))),
+        }
+    }
+}
+
+/// Nanoseconds since the Unix epoch at midnight UTC of a `YYYY-MM-DD` date.
+pub fn parse_date_ns(text: &str) -> Option<u64> {
+    let field = |range: std::ops::Range<usize>| text.get(range)?.parse::<i64>().ok();
+    if text.len() != 10 || text.get(4..5)? != "-" || text.get(7..8)? != "-" {
+        return None;
+    }
+    date_ns(field(0..4)?, field(5..7)?, field(8..10)?)
+}
+
+fn date_ns(year: i64, month: i64, day: i64) -> Option<u64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    u64::try_from(days_from_civil(year, month, day)).ok()?.checked_mul(NANOS_PER_DAY)
+}
+
+fn is_kline_interval(interval: &str) -> bool {
+    let digits = interval.find(|c: char| !c.is_ascii_digit()).unwrap_or(interval.len());
+    digits > 0 && matches!(&interval[digits..], "s" | "m" | "h" | "d" | "w" | "mo")
+}
+
+/// Archive timestamps are milliseconds, or microseconds in newer spot files.
+fn timestamp_ns(text: &str) -> Option<u64> {
+    let value = text.trim().parse::<u64>().ok()?;
+    if value >= 100_000_000_000_000 {
+        value.checked_mul(1_000)
+    } else {
+        value.checked_mul(1_000_000)
+    }
+}
+
+/// Parse one CSV row into events, oldest first.
+fn parse_row(file: &ArchiveFile, row: &str) -> Option<Vec<TimedEvent>> {
+    let fields: Vec<&str> = row.trim_end().split(',').collect();
+    let number = |index: usize| fields.get(index)?.trim().parse::<f64>().ok();
+    let time = |index: usize| timestamp_ns(fields.get(index)?);
+    let symbol = file.symbol.clone();
+    let stamp = |time_ns: u64, event: MarketEvent| TimedEvent { exchange_time_ns: time_ns, receive_time_ns: time_ns, event };
+
+    match file.kind {
+        // agg_trade_id, price, quantity, first_trade_id, last_trade_id,
+        // transact_time, is_buyer_maker[, is_best_match]
+        ArchiveKind::AggTrades => {
+            let buyer_maker = fields.get(6)?.trim();
+            let aggressor = if buyer_maker.eq_ignore_ascii_case("true") {
+                Side::Sell
+            } else if buyer_maker.eq_ignore_ascii_case("false") {
+                Side::Buy
+            } else {
+                return None;
+            };
+            let event = MarketEvent::Trade { symbol, price: number(1)?, quantity: number(2)?, aggressor };
+            Some(vec![stamp(time(5)?, event)])
+        }
+        // update_id, best_bid_price, best_bid_qty, best_ask_price,
+        // best_ask_qty, transaction_time, event_time
+        ArchiveKind::BookTicker => {
+            let event = MarketEvent::Depth {
+                symbol,
+                bids: vec![(number(1)?, number(2)?)],
+                asks: vec![(number(3)?, number(4)?)],
+            };
+            Some(vec![stamp(time(5)?, event)])
+        }
+        // open_time, open, high, low, close, volume, close_time, ...
+        ArchiveKind::Klines => {
+            let (open_ns, close_ns) = (time(0)?, time(6)?);
+            let (open, high, low, close) = (number(1)?, number(2)?, number(3)?, number(4)?);
+            if close_ns < open_ns {
+                return None;
+            }
+            // A rising bar more likely dipped before it rallied
+            let (first, second) = if close >= open { (low, high) } else { (high, low) };
+            let third = (close_ns - open_ns) / 3;
+            let prints = [(open_ns, open), (open_ns + third, first), (open_ns + 2 * third, second), (close_ns, close)];
+            Some(
+                prints
+                    .into_iter()
+                    .map(|(time_ns, price)| stamp(time_ns, MarketEvent::Ticker { symbol: symbol.clone(), last_price: price }))
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// Reads the events of one file in order.
+struct FileReader {
+    file: ArchiveFile,
+    lines: Box<dyn BufRead + Send>,
+    line_number: usize,
+    pending: VecDeque<TimedEvent>,
+}
+
+impl FileReader {
+    fn next_event(&mut self) -> Result<Option<TimedEvent>, FeedError> {
+        let mut line = String::new();
+        while self.pending.is_empty() {
+            line.clear();
+            let read = self
+                .lines
+                .read_line(&mut line)
+                .map_err(|e| FeedError::Archive(format!("{}: {}", self.file.path.display(), e)))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let row = line.trim();
+            // Header rows start with a column name instead of a number
+            if row.is_empty() || (self.line_number == 1 && !row.starts_with(|c: char| c.is_ascii_digit())) {
+                continue;
+            }
+            let events = parse_row(&self.file, row).ok_or_else(|| {
+                FeedError::Archive(format!("{}:{}: malformed row", self.file.path.display(), self.line_number))
+            })?;
+            self.pending.extend(events);
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+/// The files of one symbol and data type, read one after another.
+struct Stream {
+    files: VecDeque<ArchiveFile>,
+    reader: Option<FileReader>,
+}
+
+impl Stream {
+    fn next_event(&mut self, filter: &ArchiveFilter) -> Result<Option<TimedEvent>, FeedError> {
+        loop {
+            if self.reader.is_none() {
+                let Some(file) = self.files.pop_front() else {
+                    return Ok(None);
+                };
+                let lines = file.open()?;
+                self.reader = Some(FileReader { file, lines, line_number: 0, pending: VecDeque::new() });
+            }
+            match self.reader.as_mut().unwrap().next_event()? {
+                Some(timed) if filter.includes_time(timed.exchange_time_ns) => return Ok(Some(timed)),
+                Some(_) => {}
+                None => self.reader = None,
+            }
+        }
+    }
+}
+
+/// Merges Binance archive files into one time-ordered event sequence.
+///
+/// Files are grouped into streams by symbol and data type and each stream is
+/// read in date order; a daily file already covered by a monthly one is
+/// skipped. Streams are merged by event time, so a directory holding trades,
+/// book tickers and klines for several symbols replays as one feed.
+pub struct ArchiveImporter {
+    filter: ArchiveFilter,
+    streams: Vec<Stream>,
+    /// Next event of each stream, ordered by time and then stream index.
+    heads: BinaryHeap<Reverse<(u64, usize)>>,
+    pending: Vec<Option<TimedEvent>>,
+    started: bool,
+}
+
+impl ArchiveImporter {
+    /// Collect archive files from `paths`, descending into directories.
+    /// Files named explicitly must be recognizable archives; unrecognized
+    /// files inside directories are ignored.
+    pub fn new(paths: &[PathBuf], filter: ArchiveFilter) -> Result<Self, FeedError> {
+        let mut files = Vec::new();
+        for path in paths {
+            collect_files(path, true, &mut files)?;
+        }
+        files.retain(|file| filter.includes_symbol(&file.symbol) && filter.overlaps(file.start_ns, file.end_ns));
+
+        let mut grouped: HashMap<(String, String), Vec<ArchiveFile>> = HashMap::new();
+        for file in files {
+            grouped.entry((file.symbol.clone(), file.interval.clone())).or_default().push(file);
+        }
+        let mut keys: Vec<_> = grouped.keys().cloned().collect();
+        keys.sort();
+        let streams: Vec<Stream> = keys
+            .into_iter()
+            .map(|key| {
+                let mut files = grouped.remove(&key).unwrap();
+                files.sort_by_key(|file| (file.start_ns, Reverse(file.end_ns)));
+                let mut covered_until = 0;
+                files.retain(|file| {
+                    let new = file.end_ns > covered_until;
+                    covered_until = covered_until.max(file.end_ns);
+                    new
+                });
+                Stream { files: files.into(), reader: None }
+            })
+            .collect();
+
+        let pending = streams.iter().map(|_| None).collect();
+        Ok(Self { filter, streams, heads: BinaryHeap::new(), pending, started: false })
+    }
+
+    /// Number of files that will be read.
+    pub fn file_count(&self) -> usize {
+        self.streams.iter().map(|stream| stream.files.len()).sum()
+    }
+
+    fn advance(&mut self, index: usize) -> Result<(), FeedError> {
+        if let Some(timed) = self.streams[index].next_event(&self.filter)? {
+            self.heads.push(Reverse((timed.exchange_time_ns, index)));
+            self.pending[index] = Some(timed);
+        }
+        Ok(())
+    }
+
+    /// Next event across all streams, `None` when every file is exhausted.
+    pub fn next_event(&mut self) -> Result<Option<TimedEvent>, FeedError> {
+        if !self.started {
+            self.started = true;
+            for index in 0..self.streams.len() {
+                self.advance(index)?;
+            }
+        }
+        let Some(Reverse((_, index))) = self.heads.pop() else {
+            return Ok(None);
+        };
+        let timed = self.pending[index].take();
+        self.advance(index)?;
+        Ok(timed)
+    }
+
+    /// Write every remaining event to a new capture file at `path` that
+    /// `ReplaySource` can play back. Returns the number of events written.
+    pub fn write_capture(&mut self, path: impl AsRef<Path>) -> Result<u64, FeedError> {
+        let path = path.as_ref();
+        let capture_err = |e: std::io::Error| FeedError::Capture(format!("{}: {}", path.display(), e));
+        let mut out = BufWriter::new(File::create(path).map_err(capture_err)?);
+        capture::write_header(&mut out).map_err(capture_err)?;
+        let mut written = 0;
+        while let Some(timed) = self.next_event()? {
+            capture::write_event(&mut out, &timed).map_err(capture_err)?;
+            written += 1;
+        }
+        out.flush().map_err(capture_err)?;
+        Ok(written)
+    }
+}
+
+impl Iterator for ArchiveImporter {
+    type Item = Result<TimedEvent, FeedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+fn collect_files(path: &Path, explicit: bool, files: &mut Vec<ArchiveFile>) -> Result<(), FeedError> {
+    let archive_err = |e: std::io::Error| FeedError::Archive(format!("{}: {}", path.display(), e));
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .map_err(archive_err)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()
+            .map_err(archive_err)?;
+        entries.sort();
+        for entry in entries {
+            collect_files(&entry, false, files)?;
+        }
+        return Ok(());
+    }
+    match ArchiveFile::from_path(path) {
+        Some(file) => files.push(file),
+        None if explicit => {
+            return Err(FeedError::Archive(format!("{}: not a Binance archive file name", path.display())));
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    /// 2024-01-15T00:00:00Z in milliseconds.
+    const DAY_MS: u64 = 1_705_276_800_000;
+    const MS: u64 = 1_000_000;
+
+    /// Directory of archive files, removed when dropped.
+    struct Dir(PathBuf);
+
+    impl Dir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tradechest-archive-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        /// The directory itself as the importer's only input.
+        fn paths(&self) -> Vec<PathBuf> {
+            vec![self.0.clone()]
+        }
+
+        fn csv(&self, name: &str, rows: &[String]) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::write(&path, rows.join("\n")).unwrap();
+            path
+        }
+
+        fn zip(&self, name: &str, rows: &[String], method: CompressionMethod) -> PathBuf {
+            let path = self.0.join(name);
+            let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+            zip.start_file(name.replace(".zip", ".csv"), FileOptions::default().compression_method(method)).unwrap();
+            zip.write_all(rows.join("\n").as_bytes()).unwrap();
+            zip.finish().unwrap();
+            path
+        }
+    }
+
+    impl Drop for Dir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn agg_trade(time_ms: u64, price: f64, buyer_maker: bool) -> String {
+        format!("1,{},0.5,1,1,{},{},True", price, time_ms, if buyer_maker { "True" } else { "False" })
+    }
+
+    fn import(paths: &[PathBuf], filter: ArchiveFilter) -> Vec<TimedEvent> {
+        ArchiveImporter::new(paths, filter).unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    fn file(name: &str) -> Option<ArchiveFile> {
+        ArchiveFile::from_path(Path::new(name))
+    }
+
+    #[test]
+    fn recognizes_archive_names() {
+        let day_ns = DAY_MS * MS;
+        let trades = file("data/btcusdt-aggTrades-2024-01-15.zip").unwrap();
+        assert_eq!((trades.symbol.as_str(), trades.kind, trades.interval.as_str()), ("BTCUSDT", ArchiveKind::AggTrades, "aggTrades"));
+        assert_eq!((trades.start_ns, trades.end_ns), (day_ns, day_ns + NANOS_PER_DAY));
+
+        let ticker = file("ETHUSDT-bookTicker-2024-01-15.csv").unwrap();
+        assert_eq!(ticker.kind, ArchiveKind::BookTicker);
+
+        // Monthly files run to the first of the next month, across the year end
+        let klines = file("BTCUSDT-1m-2023-12.zip").unwrap();
+        assert_eq!((klines.kind, klines.interval.as_str()), (ArchiveKind::Klines, "1m"));
+        assert_eq!(klines.start_ns, parse_date_ns("2023-12-01").unwrap());
+        assert_eq!(klines.end_ns, parse_date_ns("2024-01-01").unwrap());
+        assert_eq!(file("BTCUSDT-1mo-2024-01.zip").unwrap().kind, ArchiveKind::Klines);
+
+        for name in [
+            "BTCUSDT-aggTrades-2024-01-15.zip.CHECKSUM",
+            "BTCUSDT-trades-2024-01-15.zip",
+            "BTCUSDT-aggTrades-2024-13-01.zip",
+            "BTCUSDT-aggTrades-2024-01-15-extra.zip",
+            "BTCUSDT-1x-2024-01.zip",
+            "capture.tctk",
+        ] {
+            assert_eq!(file(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn reads_millisecond_and_microsecond_timestamps() {
+        assert_eq!(timestamp_ns("1705276800000"), Some(DAY_MS * MS));
+        assert_eq!(timestamp_ns("1705276800000123"), Some(DAY_MS * MS + 123_000));
+        assert_eq!(timestamp_ns(" 1705276800000 "), Some(DAY_MS * MS));
+        assert_eq!(timestamp_ns("17052768e5"), None);
+        assert_eq!(parse_date_ns("2024-01-15"), Some(DAY_MS * MS));
+        assert_eq!(parse_date_ns("2024/01/15"), None);
+    }
+
+    #[test]
+    fn parses_trades_and_book_tickers() {
+        let trades = file("BTCUSDT-aggTrades-2024-01-15.csv").unwrap();
+        let events = parse_row(&trades, &agg_trade(DAY_MS, 42_000.5, true)).unwrap();
+        assert_eq!(events[0].exchange_time_ns, DAY_MS * MS);
+        assert_eq!(events[0].receive_time_ns, DAY_MS * MS);
+        // The buyer made the market, so the seller took it
+        let expected = MarketEvent::Trade { symbol: "BTCUSDT".into(), price: 42_000.5, quantity: 0.5, aggressor: Side::Sell };
+        assert_eq!(events[0].event, expected);
+        assert!(matches!(parse_row(&trades, &agg_trade(DAY_MS, 1.0, false)).unwrap()[0].event, MarketEvent::Trade { aggressor: Side::Buy, .. }));
+        assert_eq!(parse_row(&trades, "1,42000.5,0.5,1,1,1705276800000,maybe"), None);
+
+        let ticker = file("BTCUSDT-bookTicker-2024-01-15.csv").unwrap();
+        let events = parse_row(&ticker, &format!("7,100.0,1.5,100.5,2.5,{},{}", DAY_MS, DAY_MS)).unwrap();
+        let expected = MarketEvent::Depth { symbol: "BTCUSDT".into(), bids: vec![(100.0, 1.5)], asks: vec![(100.5, 2.5)] };
+        assert_eq!(events[0].event, expected);
+    }
+
+    #[test]
+    fn expands_klines_into_four_prints() {
+        let klines = file("BTCUSDT-1m-2024-01.csv").unwrap();
+        let prices = |row: String| -> Vec<(u64, f64)> {
+            parse_row(&klines, &row)
+                .unwrap()
+                .into_iter()
+                .map(|timed| match timed.event {
+                    MarketEvent::Ticker { last_price, .. } => ((timed.exchange_time_ns / MS - DAY_MS), last_price),
+                    other => panic!("expected ticker, got {:?}", other),
+                })
+                .collect()
+        };
+        let close_ms = DAY_MS + 59_999;
+        // Rising bar: open, low, high, close
+        let rising = prices(format!("{},100,110,95,105,12.5,{},1312.5,10,6,656,0", DAY_MS, close_ms));
+        assert_eq!(rising, vec![(0, 100.0), (19_999, 95.0), (39_999, 110.0), (59_999, 105.0)]);
+        // Falling bar: open, high, low, close
+        let falling = prices(format!("{},100,110,90,95,12.5,{},1312.5,10,6,656,0", DAY_MS, close_ms));
+        assert_eq!(falling.iter().map(|&(_, price)| price).collect::<Vec<_>>(), vec![100.0, 110.0, 90.0, 95.0]);
+        assert_eq!(parse_row(&klines, &format!("{},100,110,90,95,1,{}", close_ms, DAY_MS)), None);
+    }
+
+    #[test]
+    fn merges_files_by_time_skipping_headers_and_filtering_dates_and_symbols() {
+        let dir = Dir::new("merge");
+        let header = "agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker,is_best_match".to_string();
+        dir.zip(
+            "BTCUSDT-aggTrades-2024-01-15.zip",
+            &[header.clone(), agg_trade(DAY_MS + 1_000, 1.0, true), agg_trade(DAY_MS + 3_000, 3.0, true)],
+            CompressionMethod::Deflated,
+        );
+        dir.zip("BTCUSDT-aggTrades-2024-01-16.zip", &[agg_trade(DAY_MS + 86_401_000, 5.0, true)], CompressionMethod::Stored);
+        dir.csv("ETHUSDT-aggTrades-2024-01-15.csv", &[header, agg_trade(DAY_MS + 2_000, 2.0, false)]);
+        dir.csv("README.txt", &["not an archive".to_string()]);
+
+        let prices = |events: &[TimedEvent]| -> Vec<f64> {
+            events
+                .iter()
+                .map(|timed| match timed.event {
+                    MarketEvent::Trade { price, .. } => price,
+                    _ => f64::NAN,
+                })
+                .collect()
+        };
+        let all = import(&dir.paths(), ArchiveFilter::default());
+        assert_eq!(prices(&all), vec![1.0, 2.0, 3.0, 5.0]);
+        assert!(all.windows(2).all(|pair| pair[0].exchange_time_ns <= pair[1].exchange_time_ns));
+
+        let day = ArchiveFilter {
+            symbols: vec!["btcusdt".into()],
+            start_ns: Some(DAY_MS * MS + 2_000 * MS),
+            end_ns: Some(DAY_MS * MS + NANOS_PER_DAY),
+        };
+        assert_eq!(prices(&import(&dir.paths(), day)), vec![3.0]);
+
+        let mut importer = ArchiveImporter::new(&dir.paths(), ArchiveFilter::default()).unwrap();
+        assert_eq!(importer.file_count(), 3);
+        let output = dir.0.join("import.tctk");
+        assert_eq!(importer.write_capture(&output).unwrap(), 4);
+        assert!(std::fs::metadata(&output).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn prefers_monthly_files_over_the_days_they_cover() {
+        let dir = Dir::new("monthly");
+        let kline = |open_ms: u64, price: f64| format!("{},{p},{p},{p},{p},1,{},1,1,1,1,0", open_ms, open_ms + 59_999, p = price);
+        dir.csv("BTCUSDT-1m-2024-01.csv", &[kline(DAY_MS, 1.0)]);
+        dir.csv("BTCUSDT-1m-2024-01-15.csv", &[kline(DAY_MS, 2.0)]);
+        let importer = ArchiveImporter::new(&dir.paths(), ArchiveFilter::default()).unwrap();
+        assert_eq!(importer.file_count(), 1);
+        assert_eq!(importer.count(), 4);
+    }
+
+    #[test]
+    fn reports_unknown_files_and_malformed_rows() {
+        let dir = Dir::new("errors");
+        let readme = dir.csv("README.csv", &[]);
+        assert!(ArchiveImporter::new(&[readme], ArchiveFilter::default()).is_err());
+
+        let bad = dir.csv("BTCUSDT-aggTrades-2024-01-15.csv", &[agg_trade(DAY_MS, 1.0, true), "1,oops".to_string()]);
+        let imported: Result<Vec<_>, _> = ArchiveImporter::new(&[bad], ArchiveFilter::default()).unwrap().collect();
+        let error = imported.unwrap_err().to_string();
+        assert!(error.contains("BTCUSDT-aggTrades-2024-01-15.csv:2"), "{}", error);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrivalConfig {
+    /// Trades older than this are dropped from the fit.
+    pub window: Duration,
+    /// Spacing of the distance grid, relative to the mid.
+    pub step_bps: f64,
+    /// Number of grid points, starting at zero distance.
+    pub levels: usize,
+    /// No estimate is produced from fewer trades than this.
+    pub min_trades: usize,
+}
+
+impl Default for ArrivalConfig {
+    fn default() -> Self {
+        Self { window: Duration::from_secs(300), step_bps: 0.5, levels: 10, min_trades: 50 }
+    }
+}
+
+/// Fitted fill intensity `λ(δ) = A·exp(−k·δ)`, with `δ` the distance from
+/// the mid in price units and `λ` in trades per second.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ArrivalEstimate {
+    pub a: f64,
+    pub k: f64,
+    /// Trades in the window the fit was made from.
+    pub trade_count: u64,
+}
+
+/// Rolling window of trade distances from the prevailing mid, fitted to the
+/// exponential intensity assumed by Avellaneda-Stoikov.
+///
+/// A quote resting `δ` from the mid is filled by any trade reaching at least
+/// that far, so the intensity at `δ` is the rate of trades with distance
+/// `>= δ`. Rates are counted on a grid of distances and `ln λ = ln A − k·δ`
+/// is fitted by least squares.
+#[derive(Debug)]
+pub struct ArrivalEstimator {
+    config: ArrivalConfig,
+    window_ns: u64,
+    /// `(time_ns, distance)` of each trade, oldest first.
+    trades: VecDeque<(u64, f64)>,
+    /// Mid at the latest trade, which sets the grid spacing in price units.
+    last_mid: f64,
+}
+
+impl ArrivalEstimator {
+    pub fn new(config: ArrivalConfig) -> Self {
+        Self { window_ns: config.window.as_nanos() as u64, config, trades: VecDeque::new(), last_mid: 0.0 }
+    }
+
+    pub fn config(&self) -> &ArrivalConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: ArrivalConfig) {
+        self.window_ns = config.window.as_nanos() as u64;
+        self.config = config;
+    }
+
+    /// Record a trade at `price` while the mid was `mid`.
+    pub fn record(&mut self, time_ns: u64, price: f64, mid: f64) {
+        if mid <= 0.0 {
+            return;
+        }
+        self.trades.push_back((time_ns, (price - mid).abs()));
+        self.last_mid = mid;
+        self.evict(time_ns);
+    }
+
+    /// Drop every trade in the window.
+    pub fn clear(&mut self) {
+        self.trades.clear();
+    }
+
+    /// Drop trades older than the window ending at `now_ns`.
+    pub fn evict(&mut self, now_ns: u64) {
+        let cutoff = now_ns.saturating_sub(self.window_ns);
+        while self.trades.front().is_some_and(|&(time_ns, _)| time_ns < cutoff) {
+            self.trades.pop_front();
+        }
+    }
+
+    /// Fit over the trades currently in the window. `None` until there are
+    /// `min_trades` trades reaching at least two grid points, or if the fit
+    /// does not decay with distance. Rates are per full window, so `A` reads
+    /// low until the feed has run for one window.
+    pub fn estimate(&self) -> Option<ArrivalEstimate> {
+        let step = self.last_mid * self.config.step_bps / 10_000.0;
+        if self.trades.len() < self.config.min_trades.max(1) || step <= 0.0 || self.config.levels < 2 {
+            return None;
+        }
+        let window_secs = self.config.window.as_secs_f64();
+
+        let mut counts = vec![0u64; self.config.levels];
+        for &(_, distance) in &self.trades {
+            // A trade at distance d fills every grid point at or inside it
+            let reached = ((distance / step).floor() as usize + 1).min(self.config.levels);
+            for count in &mut counts[..reached] {
+                *count += 1;
+            }
+        }
+        let points: Vec<(f64, f64)> = counts
+            .iter()
+            .enumerate()
+            .take_while(|&(_, &count)| count > 0)
+            .map(|(level, &count)| (level as f64 * step, (count as f64 / window_secs).ln()))
+            .collect();
+
+        let (slope, intercept) = least_squares(&points)?;
+        (slope < 0.0).then(|| ArrivalEstimate {
+            a: intercept.exp(),
+            k: -slope,
+            trade_count: self.trades.len() as u64,
+        })
+    }
+}
+
+/// Slope and intercept of the least-squares line through `points`.
+fn least_squares(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|&(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Estimator fed `trades` fills over one 300 s window around a mid of
+    /// 100, at distances drawn so that fills reach `δ` at rate `A·exp(−k·δ)`
+    /// with `A = trades / 300`.
+    fn fitted(k: f64, trades: usize, seed: u64) -> ArrivalEstimator {
+        let config = ArrivalConfig { step_bps: 10.0, levels: 10, ..ArrivalConfig::default() };
+        let window_ns = config.window.as_nanos() as u64;
+        let mut estimator = ArrivalEstimator::new(config);
+        let mut rng = fastrand::Rng::with_seed(seed);
+        for i in 0..trades {
+            // P(distance >= δ) = exp(−k·δ)
+            let distance = -(1.0 - rng.f64()).ln() / k;
+            let price = if rng.bool() { 100.0 + distance } else { 100.0 - distance };
+            estimator.record(i as u64 * window_ns / trades as u64, price, 100.0);
+        }
+        estimator
+    }
+
+    #[test]
+    fn least_squares_fit_recovers_known_intensity() {
+        for (k, trades, seed) in [(5.0, 30_000, 1), (2.0, 12_000, 2), (8.0, 60_000, 3)] {
+            let estimate = fitted(k, trades, seed).estimate().unwrap();
+            let a = trades as f64 / 300.0;
+            assert_eq!(estimate.trade_count, trades as u64);
+            assert!((estimate.k / k - 1.0).abs() < 0.05, "k {} fitted as {}", k, estimate.k);
+            assert!((estimate.a / a - 1.0).abs() < 0.05, "A {} fitted as {}", a, estimate.a);
+        }
+    }
+
+    #[test]
+    fn needs_enough_trades_that_decay_with_distance() {
+        assert_eq!(fitted(5.0, 49, 4).estimate(), None);
+        assert!(fitted(5.0, 50, 4).estimate().is_some());
+
+        // Every trade at the mid reaches only the first grid point
+        let mut estimator = ArrivalEstimator::new(ArrivalConfig::default());
+        for i in 0..100 {
+            estimator.record(i, 100.0, 100.0);
+        }
+        assert_eq!(estimator.estimate(), None);
+
+        // Trades leave the window as time moves on
+        let mut estimator = fitted(5.0, 1_000, 5);
+        estimator.evict(2 * estimator.window_ns);
+        assert_eq!(estimator.estimate(), None);
+    }
+}
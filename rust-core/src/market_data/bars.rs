@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Completed bars kept per series unless configured otherwise.
+pub const DEFAULT_BAR_HISTORY: usize = 1000;
+
+/// One OHLCV bar. Times are the feed clock of the first and last update
+/// folded into the bar, in nanoseconds since the Unix epoch.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bar {
+    pub open_time_ns: u64,
+    pub close_time_ns: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded base quantity.
+    pub volume: f64,
+    /// Traded quote notional, `sum(price * quantity)`.
+    pub dollar_volume: f64,
+    pub trade_count: u64,
+}
+
+impl Bar {
+    fn open_at(time_ns: u64, price: f64) -> Self {
+        Self {
+            open_time_ns: time_ns,
+            close_time_ns: time_ns,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            ..Self::default()
+        }
+    }
+
+    fn update(&mut self, time_ns: u64, price: f64) {
+        self.close_time_ns = time_ns;
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+    }
+}
+
+/// How a series decides when a bar is complete.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarSpec {
+    /// Fixed clock intervals aligned to the epoch (1s, 1m, 5m, ...). Intervals
+    /// without any update produce no bar.
+    Time(Duration),
+    /// Closes once traded quantity reaches the threshold.
+    Volume(f64),
+    /// Closes once traded notional reaches the threshold.
+    Dollar(f64),
+}
+
+impl BarSpec {
+    fn is_valid(&self) -> bool {
+        match *self {
+            BarSpec::Time(interval) => !interval.is_zero(),
+            BarSpec::Volume(threshold) | BarSpec::Dollar(threshold) => threshold > 0.0 && threshold.is_finite(),
+        }
+    }
+}
+
+/// Builds one bar series from trades and price updates, keeping a bounded
+/// history of completed bars.
+#[derive(Debug)]
+pub struct BarBuilder {
+    spec: BarSpec,
+    capacity: usize,
+    current: Option<Bar>,
+    history: VecDeque<Bar>,
+}
+
+impl BarBuilder {
+    pub fn new(spec: BarSpec, capacity: usize) -> Self {
+        Self { spec, capacity, current: None, history: VecDeque::new() }
+    }
+
+    pub fn spec(&self) -> BarSpec {
+        self.spec
+    }
+
+    /// Fold in a trade. Volume and dollar bars close on the trade that
+    /// crosses their threshold.
+    pub fn record_trade(&mut self, time_ns: u64, price: f64, quantity: f64) {
+        let spec = self.spec;
+        let bar = self.bar_for(time_ns, price);
+        bar.volume += quantity;
+        bar.dollar_volume += price * quantity;
+        bar.trade_count += 1;
+        let full = match spec {
+            BarSpec::Time(_) => false,
+            BarSpec::Volume(threshold) => bar.volume >= threshold,
+            BarSpec::Dollar(threshold) => bar.dollar_volume >= threshold,
+        };
+        if full {
+            self.close_current();
+        }
+    }
+
+    /// Fold in a price without traded quantity, such as a ticker update.
+    pub fn record_price(&mut self, time_ns: u64, price: f64) {
+        self.bar_for(time_ns, price);
+    }
+
+    /// Close the in-progress time bar once the clock has moved past its
+    /// interval, so quiet markets still complete their last bar.
+    pub fn advance(&mut self, now_ns: u64) {
+        if let (BarSpec::Time(interval), Some(bar)) = (self.spec, &self.current) {
+            let interval_ns = interval.as_nanos() as u64;
+            if now_ns / interval_ns != bar.open_time_ns / interval_ns {
+                self.close_current();
+            }
+        }
+    }
+
+    /// Bar currently being built, if any.
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Completed bars, oldest first.
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Bar> + ExactSizeIterator {
+        self.history.iter()
+    }
+
+    /// Up to `count` most recent bars, oldest first, ending with the
+    /// in-progress bar.
+    pub fn latest(&self, count: usize) -> Vec<Bar> {
+        let completed = count.saturating_sub(self.current.is_some() as usize).min(self.history.len());
+        let mut bars: Vec<Bar> = self.history.iter().skip(self.history.len() - completed).copied().collect();
+        bars.extend(self.current.iter().take(count).copied());
+        bars
+    }
+
+    /// Current bar to update for an event at `time_ns`, rolling time bars
+    /// over when the event falls in a later interval.
+    fn bar_for(&mut self, time_ns: u64, price: f64) -> &mut Bar {
+        self.advance(time_ns);
+        match self.current {
+            Some(ref mut bar) => bar.update(time_ns, price),
+            None => self.current = Some(Bar::open_at(time_ns, price)),
+        }
+        self.current.as_mut().unwrap()
+    }
+
+    fn close_current(&mut self) {
+        if let Some(bar) = self.current.take() {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+            if self.capacity > 0 {
+                self.history.push_back(bar);
+            }
+        }
+    }
+}
+
+/// All bar series maintained for one symbol.
+#[derive(Debug)]
+pub struct BarAggregator {
+    builders: Vec<BarBuilder>,
+    capacity: usize,
+}
+
+impl Default for BarAggregator {
+    /// 1s, 1m and 5m time bars.
+    fn default() -> Self {
+        let mut aggregator = Self::new(DEFAULT_BAR_HISTORY);
+        for secs in [1, 60, 300] {
+            aggregator.add_series(BarSpec::Time(Duration::from_secs(secs)));
+        }
+        aggregator
+    }
+}
+
+impl BarAggregator {
+    pub fn new(capacity: usize) -> Self {
+        Self { builders: Vec::new(), capacity }
+    }
+
+    /// Start building `spec` bars from the next update on. Returns `false`
+    /// if the spec is invalid; adding an existing series is a no-op.
+    pub fn add_series(&mut self, spec: BarSpec) -> bool {
+        if !spec.is_valid() {
+            return false;
+        }
+        if self.series(spec).is_none() {
+            self.builders.push(BarBuilder::new(spec, self.capacity));
+        }
+        true
+    }
+
+    pub fn remove_series(&mut self, spec: BarSpec) {
+        self.builders.retain(|builder| builder.spec != spec);
+    }
+
+    pub fn series(&self, spec: BarSpec) -> Option<&BarBuilder> {
+        self.builders.iter().find(|builder| builder.spec == spec)
+    }
+
+    /// Drop the bars of every series, keeping the series themselves.
+    pub fn clear(&mut self) {
+        for builder in &mut self.builders {
+            builder.current = None;
+            builder.history.clear();
+        }
+    }
+
+    pub fn record_trade(&mut self, time_ns: u64, price: f64, quantity: f64) {
+        for builder in &mut self.builders {
+            builder.record_trade(time_ns, price, quantity);
+        }
+    }
+
+    pub fn record_price(&mut self, time_ns: u64, price: f64) {
+        for builder in &mut self.builders {
+            builder.record_price(time_ns, price);
+        }
+    }
+
+    pub fn advance(&mut self, now_ns: u64) {
+        for builder in &mut self.builders {
+            builder.advance(now_ns);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn time_bars_roll_over_on_interval_boundaries() {
+        let mut bars = BarBuilder::new(BarSpec::Time(Duration::from_secs(1)), 10);
+        bars.record_trade(10 * SECOND + 100, 100.0, 1.0);
+        bars.record_price(10 * SECOND + 200, 102.0);
+        bars.record_trade(10 * SECOND + 300, 99.0, 2.0);
+        bars.record_trade(11 * SECOND, 101.0, 0.5);
+
+        let closed = bars.history().next().unwrap();
+        assert_eq!((closed.open_time_ns, closed.close_time_ns), (10 * SECOND + 100, 10 * SECOND + 300));
+        assert_eq!((closed.open, closed.high, closed.low, closed.close), (100.0, 102.0, 99.0, 99.0));
+        assert_eq!((closed.volume, closed.dollar_volume, closed.trade_count), (3.0, 298.0, 2));
+        assert_eq!(bars.current().unwrap().open, 101.0);
+    }
+
+    #[test]
+    fn advance_closes_the_last_bar_and_skips_empty_intervals() {
+        let mut bars = BarBuilder::new(BarSpec::Time(Duration::from_secs(1)), 10);
+        bars.advance(5 * SECOND);
+        assert!(bars.current().is_none());
+
+        bars.record_price(5 * SECOND, 100.0);
+        bars.advance(5 * SECOND + SECOND / 2);
+        assert!(bars.current().is_some());
+        bars.advance(9 * SECOND);
+        assert!(bars.current().is_none());
+        assert_eq!(bars.history().len(), 1);
+
+        // Quiet intervals produce no bars
+        bars.record_price(9 * SECOND, 101.0);
+        bars.advance(10 * SECOND);
+        let opens: Vec<u64> = bars.history().map(|bar| bar.open_time_ns).collect();
+        assert_eq!(opens, vec![5 * SECOND, 9 * SECOND]);
+    }
+
+    #[test]
+    fn volume_and_dollar_bars_close_on_the_trade_reaching_the_threshold() {
+        let mut volume = BarBuilder::new(BarSpec::Volume(2.0), 10);
+        let mut dollar = BarBuilder::new(BarSpec::Dollar(250.0), 10);
+        for (i, (price, quantity)) in [(100.0, 1.5), (101.0, 1.0), (99.0, 0.5), (100.0, 1.5)].into_iter().enumerate() {
+            volume.record_trade(i as u64, price, quantity);
+            dollar.record_trade(i as u64, price, quantity);
+        }
+
+        // Bars take the whole crossing trade rather than splitting it
+        let volumes: Vec<f64> = volume.history().map(|bar| bar.volume).collect();
+        assert_eq!(volumes, vec![2.5, 2.0]);
+        assert!(volume.current().is_none());
+        let notionals: Vec<f64> = dollar.history().map(|bar| bar.dollar_volume).collect();
+        assert_eq!(notionals, vec![251.0]);
+        assert_eq!(dollar.current().unwrap().dollar_volume, 199.5);
+
+        // Prices without volume never close a volume bar
+        volume.record_price(10 * SECOND, 100.0);
+        volume.advance(20 * SECOND);
+        assert!(volume.current().is_some());
+    }
+
+    #[test]
+    fn latest_ends_with_the_bar_in_progress() {
+        let mut bars = BarBuilder::new(BarSpec::Volume(1.0), 3);
+        assert!(bars.latest(5).is_empty());
+        for i in 0..5 {
+            bars.record_trade(i, 100.0 + i as f64, 1.0);
+        }
+        bars.record_trade(5, 105.0, 0.5);
+
+        // Capacity bounds the history to the three most recent completed bars
+        let closes: Vec<f64> = bars.latest(10).iter().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![102.0, 103.0, 104.0, 105.0]);
+        let closes: Vec<f64> = bars.latest(2).iter().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![104.0, 105.0]);
+        assert_eq!(bars.latest(1)[0].volume, 0.5);
+        assert!(bars.latest(0).is_empty());
+    }
+}
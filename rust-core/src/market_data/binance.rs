@@ -0,0 +1,198 @@
+use super::order_book::{BookDelta, DepthSnapshot};
+use super::source::{FeedError, MarketDataSource, MarketEvent, Side, TimedEvent};
+use super::ws::{self, WsStream};
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use std::time::Duration;
+
+pub const BINANCE_STREAM_URL: &str = "wss://stream.binance.com:9443";
+
+/// Tickers arrive every second per symbol, so this much silence means the
+/// socket is dead even if it was never closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Binance spot market streams for any number of symbols over a single
+/// combined-stream connection.
+pub struct BinanceSource {
+    base_url: String,
+    stream: Option<WsStream>,
+    symbols: Vec<String>,
+    next_request_id: u64,
+}
+
+impl BinanceSource {
+    pub fn new() -> Self {
+        Self::with_base_url(BINANCE_STREAM_URL)
+    }
+
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            stream: None,
+            symbols: Vec::new(),
+            next_request_id: 1,
+        }
+    }
+
+    fn stream_names(symbol: &str) -> Vec<String> {
+        let symbol_lower = symbol.to_lowercase();
+        vec![
+            format!("{}@ticker", symbol_lower),
+            format!("{}@depth@100ms", symbol_lower),
+            format!("{}@aggTrade", symbol_lower),
+        ]
+    }
+}
+
+impl Default for BinanceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketDataSource for BinanceSource {
+    /// Open `/stream?streams=...` for every symbol subscribed so far.
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>> {
+        Box::pin(async move {
+            let streams: Vec<String> = self.symbols.iter().flat_map(|s| Self::stream_names(s)).collect();
+            let url = format!("{}/stream?streams={}", self.base_url, streams.join("/"));
+            self.stream = Some(ws::connect(&url).await?);
+            Ok(())
+        })
+    }
+
+    /// Add `symbol` to the subscription set. Before `connect` this only
+    /// extends the stream list; on a live connection it sends a `SUBSCRIBE`.
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_lowercase();
+            if self.symbols.contains(&symbol) {
+                return Ok(());
+            }
+            let stream_names = Self::stream_names(&symbol);
+            self.symbols.push(symbol);
+            let stream = match self.stream.as_mut() {
+                Some(stream) => stream,
+                None => return Ok(()),
+            };
+
+            let id = self.next_request_id;
+            self.next_request_id += 1;
+            let request = serde_json::json!({
+                "method": "SUBSCRIBE",
+                "params": stream_names,
+                "id": id,
+            });
+            ws::send_json(stream, &request).await
+        })
+    }
+
+    /// Remove `symbol` from the subscription set, sending an `UNSUBSCRIBE`
+    /// on a live connection.
+    fn unsubscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_lowercase();
+            let Some(index) = self.symbols.iter().position(|s| *s == symbol) else {
+                return Ok(());
+            };
+            self.symbols.remove(index);
+            let stream = match self.stream.as_mut() {
+                Some(stream) => stream,
+                None => return Ok(()),
+            };
+
+            let id = self.next_request_id;
+            self.next_request_id += 1;
+            let request = serde_json::json!({
+                "method": "UNSUBSCRIBE",
+                "params": Self::stream_names(&symbol),
+                "id": id,
+            });
+            ws::send_json(stream, &request).await
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>> {
+        Box::pin(async move {
+            loop {
+                let text = ws::next_text(&mut self.stream).await?;
+                if let Some(event) = parse_message(&text) {
+                    return Ok(Some(event));
+                }
+            }
+        })
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(IDLE_TIMEOUT)
+    }
+}
+
+/// Parse a combined-stream payload (`{"stream": ..., "data": ...}`).
+/// Subscription acknowledgements and unknown streams yield `None`.
+fn parse_message(text: &str) -> Option<TimedEvent> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    let stream = message["stream"].as_str()?;
+    let data = &message["data"];
+    let event = parse_stream_data(stream, data)?;
+    // Partial depth streams carry no event time
+    let exchange_time_ns = data["E"].as_u64().unwrap_or(0) * 1_000_000;
+    Some(TimedEvent::received_now(exchange_time_ns, event))
+}
+
+fn parse_stream_data(stream: &str, data: &Value) -> Option<MarketEvent> {
+    let (symbol, kind) = stream.split_once('@')?;
+    let symbol = symbol.to_uppercase();
+
+    if kind == "ticker" {
+        let last_price = data["c"].as_str()?.parse::<f64>().ok()?;
+        Some(MarketEvent::Ticker { symbol, last_price })
+    } else if kind == "aggTrade" {
+        // `m` is set when the buyer is the maker, i.e. the seller aggressed
+        let aggressor = if data["m"].as_bool()? { Side::Sell } else { Side::Buy };
+        Some(MarketEvent::Trade {
+            symbol,
+            price: data["p"].as_str()?.parse::<f64>().ok()?,
+            quantity: data["q"].as_str()?.parse::<f64>().ok()?,
+            aggressor,
+        })
+    } else if kind.starts_with("depth") && data["e"].as_str() == Some("depthUpdate") {
+        let delta = BookDelta {
+            first_update_id: data["U"].as_u64()?,
+            final_update_id: data["u"].as_u64()?,
+            bids: parse_levels(&data["b"])?,
+            asks: parse_levels(&data["a"])?,
+        };
+        Some(MarketEvent::BookDelta { symbol, delta })
+    } else if kind.starts_with("depth") {
+        Some(MarketEvent::Depth {
+            symbol,
+            bids: parse_levels(&data["bids"])?,
+            asks: parse_levels(&data["asks"])?,
+        })
+    } else {
+        None
+    }
+}
+
+/// Parse a `GET /api/v3/depth` response body.
+pub(crate) fn parse_depth_snapshot(text: &str) -> Option<DepthSnapshot> {
+    let data: Value = serde_json::from_str(text).ok()?;
+    Some(DepthSnapshot {
+        last_update_id: data["lastUpdateId"].as_u64()?,
+        bids: parse_levels(&data["bids"])?,
+        asks: parse_levels(&data["asks"])?,
+    })
+}
+
+fn parse_levels(levels: &Value) -> Option<Vec<(f64, f64)>> {
+    levels
+        .as_array()?
+        .iter()
+        .map(|level| {
+            let price = level[0].as_str()?.parse::<f64>().ok()?;
+            let quantity = level[1].as_str()?.parse::<f64>().ok()?;
+            Some((price, quantity))
+        })
+        .collect()
+}
@@ -0,0 +1,297 @@
+//! Binary capture format for recorded feed events.
+//!
+//! A capture file starts with a 6-byte header (`TCTK` magic followed by a
+//! little-endian `u16` version) and is followed by length-prefixed records:
+//!
+//! ```text
+//! u32 payload length | u8 kind | u64 exchange_time_ns | u64 receive_time_ns
+//!                    | u8 symbol length | symbol bytes | kind-specific body
+//! ```
+//!
+//! Version 1 files stored the exchange time in milliseconds; they are still
+//! read, with the time converted to nanoseconds.
+//!
+//! All integers and floats are little-endian. Price levels are encoded as a
+//! `u32` count followed by `(f64 price, f64 quantity)` pairs.
+
+use super::order_book::{BookDelta, DepthSnapshot};
+use super::source::{FeedError, MarketEvent, Side, TimedEvent};
+use std::io::{self, Read, Write};
+
+pub const CAPTURE_MAGIC: [u8; 4] = *b"TCTK";
+pub const CAPTURE_VERSION: u16 = 2;
+pub const CAPTURE_HEADER_LEN: usize = 6;
+
+const KIND_TICKER: u8 = 1;
+const KIND_DEPTH: u8 = 2;
+const KIND_BOOK_SNAPSHOT: u8 = 3;
+const KIND_BOOK_DELTA: u8 = 4;
+const KIND_TRADE: u8 = 5;
+
+/// Upper bound on a single record, guarding against reading garbage lengths.
+const MAX_RECORD_LEN: usize = 16 * 1024 * 1024;
+
+pub fn write_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(&CAPTURE_MAGIC)?;
+    writer.write_all(&CAPTURE_VERSION.to_le_bytes())
+}
+
+/// Validate the header and return the file's format version.
+pub fn read_header<R: Read>(reader: &mut R) -> Result<u16, FeedError> {
+    let mut header = [0u8; CAPTURE_HEADER_LEN];
+    reader.read_exact(&mut header).map_err(|e| FeedError::Capture(e.to_string()))?;
+    if header[..4] != CAPTURE_MAGIC {
+        return Err(FeedError::Capture("not a tick capture file".to_string()));
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version == 0 || version > CAPTURE_VERSION {
+        return Err(FeedError::Capture(format!("unsupported capture version {}", version)));
+    }
+    Ok(version)
+}
+
+/// Append one length-prefixed record for `timed` to `writer`.
+pub fn write_event<W: Write>(writer: &mut W, timed: &TimedEvent) -> io::Result<()> {
+    let mut payload = Vec::with_capacity(64);
+    let (kind, symbol) = match &timed.event {
+        MarketEvent::Ticker { symbol, .. } => (KIND_TICKER, symbol),
+        MarketEvent::Depth { symbol, .. } => (KIND_DEPTH, symbol),
+        MarketEvent::BookSnapshot { symbol, .. } => (KIND_BOOK_SNAPSHOT, symbol),
+        MarketEvent::BookDelta { symbol, .. } => (KIND_BOOK_DELTA, symbol),
+        MarketEvent::Trade { symbol, .. } => (KIND_TRADE, symbol),
+    };
+    payload.push(kind);
+    payload.extend_from_slice(&timed.exchange_time_ns.to_le_bytes());
+    payload.extend_from_slice(&timed.receive_time_ns.to_le_bytes());
+    let symbol = &symbol.as_bytes()[..symbol.len().min(u8::MAX as usize)];
+    payload.push(symbol.len() as u8);
+    payload.extend_from_slice(symbol);
+
+    match &timed.event {
+        MarketEvent::Ticker { last_price, .. } => {
+            payload.extend_from_slice(&last_price.to_le_bytes());
+        }
+        MarketEvent::Depth { bids, asks, .. } => {
+            put_levels(&mut payload, bids);
+            put_levels(&mut payload, asks);
+        }
+        MarketEvent::BookSnapshot { snapshot, .. } => {
+            payload.extend_from_slice(&snapshot.last_update_id.to_le_bytes());
+            put_levels(&mut payload, &snapshot.bids);
+            put_levels(&mut payload, &snapshot.asks);
+        }
+        MarketEvent::BookDelta { delta, .. } => {
+            payload.extend_from_slice(&delta.first_update_id.to_le_bytes());
+            payload.extend_from_slice(&delta.final_update_id.to_le_bytes());
+            put_levels(&mut payload, &delta.bids);
+            put_levels(&mut payload, &delta.asks);
+        }
+        MarketEvent::Trade { price, quantity, aggressor, .. } => {
+            payload.extend_from_slice(&price.to_le_bytes());
+            payload.extend_from_slice(&quantity.to_le_bytes());
+            payload.push(match aggressor {
+                Side::Buy => 0,
+                Side::Sell => 1,
+            });
+        }
+    }
+
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(&payload)
+}
+
+/// Read the next record from a file of format `version`. Returns `Ok(None)`
+/// at end of file, including when the final record was only partially
+/// written.
+pub fn read_event<R: Read>(reader: &mut R, version: u16) -> Result<Option<TimedEvent>, FeedError> {
+    let mut len_bytes = [0u8; 4];
+    if !read_full(reader, &mut len_bytes)? {
+        return Ok(None);
+    }
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    if len > MAX_RECORD_LEN {
+        return Err(FeedError::Capture(format!("record length {} exceeds limit", len)));
+    }
+    let mut payload = vec![0u8; len];
+    if !read_full(reader, &mut payload)? {
+        return Ok(None);
+    }
+    decode_payload(&payload, version)
+        .map(Some)
+        .ok_or_else(|| FeedError::Capture("malformed capture record".to_string()))
+}
+
+fn decode_payload(payload: &[u8], version: u16) -> Option<TimedEvent> {
+    let mut cursor = Cursor { data: payload, pos: 0 };
+    let kind = cursor.u8()?;
+    let exchange_time_ns = match cursor.u64()? {
+        millis if version == 1 => millis.saturating_mul(1_000_000),
+        nanos => nanos,
+    };
+    let receive_time_ns = cursor.u64()?;
+    let symbol_len = cursor.u8()? as usize;
+    let symbol = String::from_utf8(cursor.bytes(symbol_len)?.to_vec()).ok()?;
+
+    let event = match kind {
+        KIND_TICKER => MarketEvent::Ticker { symbol, last_price: cursor.f64()? },
+        KIND_DEPTH => MarketEvent::Depth { symbol, bids: cursor.levels()?, asks: cursor.levels()? },
+        KIND_BOOK_SNAPSHOT => MarketEvent::BookSnapshot {
+            symbol,
+            snapshot: DepthSnapshot {
+                last_update_id: cursor.u64()?,
+                bids: cursor.levels()?,
+                asks: cursor.levels()?,
+            },
+        },
+        KIND_BOOK_DELTA => MarketEvent::BookDelta {
+            symbol,
+            delta: BookDelta {
+                first_update_id: cursor.u64()?,
+                final_update_id: cursor.u64()?,
+                bids: cursor.levels()?,
+                asks: cursor.levels()?,
+            },
+        },
+        KIND_TRADE => MarketEvent::Trade {
+            symbol,
+            price: cursor.f64()?,
+            quantity: cursor.f64()?,
+            aggressor: match cursor.u8()? {
+                0 => Side::Buy,
+                1 => Side::Sell,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    };
+    Some(TimedEvent { exchange_time_ns, receive_time_ns, event })
+}
+
+fn put_levels(payload: &mut Vec<u8>, levels: &[(f64, f64)]) {
+    payload.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+    for (price, quantity) in levels {
+        payload.extend_from_slice(&price.to_le_bytes());
+        payload.extend_from_slice(&quantity.to_le_bytes());
+    }
+}
+
+/// Fill `buf` completely. Returns `false` on a clean or truncated EOF.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, FeedError> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(FeedError::Capture(e.to_string())),
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn levels(&mut self) -> Option<Vec<(f64, f64)>> {
+        let count = self.u32()? as usize;
+        // Each level takes 16 bytes; reject counts the payload cannot hold
+        if count > (self.data.len() - self.pos) / 16 {
+            return None;
+        }
+        (0..count).map(|_| Some((self.f64()?, self.f64()?))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<TimedEvent> {
+        let symbol = || "BTCUSDT".to_string();
+        let bids = vec![(100.0, 1.5), (99.5, 2.0)];
+        let asks = vec![(100.5, 0.25)];
+        [
+            MarketEvent::Ticker { symbol: symbol(), last_price: 100.25 },
+            MarketEvent::Depth { symbol: symbol(), bids: bids.clone(), asks: asks.clone() },
+            MarketEvent::BookSnapshot {
+                symbol: symbol(),
+                snapshot: DepthSnapshot { last_update_id: 41, bids: bids.clone(), asks: Vec::new() },
+            },
+            MarketEvent::BookDelta {
+                symbol: symbol(),
+                delta: BookDelta { first_update_id: 42, final_update_id: 44, bids: Vec::new(), asks },
+            },
+            MarketEvent::Trade { symbol: symbol(), price: 100.5, quantity: 0.75, aggressor: Side::Buy },
+            MarketEvent::Trade { symbol: symbol(), price: 100.0, quantity: 0.5, aggressor: Side::Sell },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, event)| TimedEvent { exchange_time_ns: 1_700_000_000_000_000_000 + i as u64, receive_time_ns: 5 + i as u64, event })
+        .collect()
+    }
+
+    fn encode(events: &[TimedEvent]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_header(&mut bytes).unwrap();
+        for timed in events {
+            write_event(&mut bytes, timed).unwrap();
+        }
+        bytes
+    }
+
+    fn decode(mut bytes: &[u8]) -> Vec<TimedEvent> {
+        let version = read_header(&mut bytes).unwrap();
+        std::iter::from_fn(|| read_event(&mut bytes, version).unwrap()).collect()
+    }
+
+    #[test]
+    fn round_trips_every_event_kind() {
+        let events = events();
+        assert_eq!(decode(&encode(&events)), events);
+    }
+
+    #[test]
+    fn stops_at_a_truncated_final_record() {
+        let events = events();
+        let bytes = encode(&events);
+        assert_eq!(decode(&bytes[..bytes.len() - 3]), events[..events.len() - 1]);
+        // A length prefix cut short counts as the end of the file too
+        let complete = encode(&events[..2]).len();
+        assert_eq!(decode(&bytes[..complete + 2]), events[..2]);
+    }
+
+    #[test]
+    fn converts_version_1_millisecond_exchange_times() {
+        let timed = TimedEvent { exchange_time_ns: 1_700_000_000_123, receive_time_ns: 7, event: events()[0].event.clone() };
+        let mut bytes = encode(std::slice::from_ref(&timed));
+        bytes[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert_eq!(decode(&bytes), vec![TimedEvent { exchange_time_ns: 1_700_000_000_123_000_000, ..timed }]);
+    }
+
+    #[test]
+    fn rejects_foreign_and_future_headers() {
+        assert!(read_header(&mut &b"RIFF\x02\x00"[..]).is_err());
+        let future = (CAPTURE_VERSION + 1).to_le_bytes();
+        assert!(read_header(&mut &[b'T', b'C', b'T', b'K', future[0], future[1]][..]).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use super::order_book::BookSequencer;
+use super::source::{parse_rfc3339_ns, FeedError, MarketDataSource, MarketEvent, Side, TimedEvent};
+use super::ws::{self, WsStream};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub const COINBASE_STREAM_URL: &str = "wss://advanced-trade-ws.coinbase.com";
+
+/// Tickers arrive whenever the price moves and heartbeats every second.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CHANNELS: [&str; 3] = ["ticker", "market_trades", "level2"];
+
+/// Coinbase Advanced Trade public market data (`ticker`, `market_trades`
+/// and `level2` channels) for any number of products over one connection.
+/// Symbols are Coinbase product ids such as `BTC-USD`.
+///
+/// Every message on the connection carries the next `sequence_num`. A skip
+/// means messages were lost, so every book is held until `level2` is
+/// resubscribed and sends fresh snapshots.
+pub struct CoinbaseSource {
+    base_url: String,
+    stream: Option<WsStream>,
+    symbols: Vec<String>,
+    books: BookSequencer,
+    pending: VecDeque<TimedEvent>,
+}
+
+impl CoinbaseSource {
+    pub fn new() -> Self {
+        Self::with_base_url(COINBASE_STREAM_URL)
+    }
+
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            stream: None,
+            symbols: Vec::new(),
+            books: BookSequencer::default(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn subscribe_requests(product_ids: &[String]) -> impl Iterator<Item = Value> + '_ {
+        // Heartbeats keep the connection open for quiet products
+        CHANNELS
+            .iter()
+            .map(move |channel| json!({ "type": "subscribe", "product_ids": product_ids, "channel": channel }))
+            .chain(std::iter::once(json!({ "type": "subscribe", "channel": "heartbeats" })))
+    }
+
+    /// Resubscribe `level2` for books that missed updates, which makes
+    /// Coinbase send a new snapshot of each.
+    async fn resync_books(&mut self) -> Result<(), FeedError> {
+        let product_ids = self.books.take_resync();
+        if product_ids.is_empty() {
+            return Ok(());
+        }
+        log::warn!("Coinbase messages lost, resubscribing level2 for {}", product_ids.join(", "));
+        if let Some(stream) = self.stream.as_mut() {
+            for kind in ["unsubscribe", "subscribe"] {
+                ws::send_json(stream, &json!({ "type": kind, "product_ids": product_ids, "channel": "level2" })).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for CoinbaseSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketDataSource for CoinbaseSource {
+    /// Connect and subscribe every product added so far.
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>> {
+        Box::pin(async move {
+            self.pending.clear();
+            self.books.reconnected();
+            let mut stream = ws::connect(&self.base_url).await?;
+            for request in Self::subscribe_requests(&self.symbols) {
+                ws::send_json(&mut stream, &request).await?;
+            }
+            self.stream = Some(stream);
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_uppercase();
+            if self.symbols.contains(&symbol) {
+                return Ok(());
+            }
+            self.symbols.push(symbol.clone());
+            if let Some(stream) = self.stream.as_mut() {
+                for request in Self::subscribe_requests(&[symbol]) {
+                    ws::send_json(stream, &request).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn unsubscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_uppercase();
+            let Some(index) = self.symbols.iter().position(|s| *s == symbol) else {
+                return Ok(());
+            };
+            self.symbols.remove(index);
+            if let Some(stream) = self.stream.as_mut() {
+                for channel in CHANNELS {
+                    ws::send_json(stream, &json!({ "type": "unsubscribe", "product_ids": [&symbol], "channel": channel })).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>> {
+        Box::pin(async move {
+            while self.pending.is_empty() {
+                let text = ws::next_text(&mut self.stream).await?;
+                self.pending.extend(parse_message(&text, &mut self.books));
+                self.resync_books().await?;
+            }
+            Ok(self.pending.pop_front())
+        })
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(IDLE_TIMEOUT)
+    }
+}
+
+/// Parse one channel message into normalized events. Subscription
+/// confirmations, heartbeats and unknown channels yield nothing, except
+/// that a skipped `sequence_num` yields a gap delta for every book.
+fn parse_message(text: &str, books: &mut BookSequencer) -> Vec<TimedEvent> {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(_) => return Vec::new(),
+    };
+    let exchange_time_ns = message["timestamp"].as_str().and_then(parse_rfc3339_ns).unwrap_or(0);
+
+    let mut parsed = Vec::new();
+    // Any channel's message may have been lost, so every book is suspect
+    if message["sequence_num"].as_u64().is_some_and(|number| books.message_number(number)) {
+        for (symbol, delta) in books.gap_all() {
+            parsed.push(TimedEvent::received_now(exchange_time_ns, MarketEvent::BookDelta { symbol, delta }));
+        }
+    }
+    let events = match message["events"].as_array() {
+        Some(events) => events,
+        None => return parsed,
+    };
+
+    for event in events {
+        match message["channel"].as_str() {
+            Some("ticker") | Some("ticker_batch") => {
+                for ticker in event["tickers"].as_array().into_iter().flatten() {
+                    if let Some(event) = parse_ticker(ticker) {
+                        parsed.push(TimedEvent::received_now(exchange_time_ns, event));
+                    }
+                }
+            }
+            // The initial snapshot replays historical prints; only live trades count as flow
+            Some("market_trades") if event["type"].as_str() == Some("update") => {
+                for trade in event["trades"].as_array().into_iter().flatten() {
+                    if let Some(event) = parse_trade(trade) {
+                        let time_ns = trade["time"].as_str().and_then(parse_rfc3339_ns).unwrap_or(exchange_time_ns);
+                        parsed.push(TimedEvent::received_now(time_ns, event));
+                    }
+                }
+            }
+            Some("l2_data") => {
+                if let Some(event) = parse_level2(event, books) {
+                    parsed.push(TimedEvent::received_now(exchange_time_ns, event));
+                }
+            }
+            _ => {}
+        }
+    }
+    parsed
+}
+
+fn parse_ticker(ticker: &Value) -> Option<MarketEvent> {
+    Some(MarketEvent::Ticker {
+        symbol: ticker["product_id"].as_str()?.to_string(),
+        last_price: parse_number(&ticker["price"])?,
+    })
+}
+
+/// `side` is the taker side of the trade.
+fn parse_trade(trade: &Value) -> Option<MarketEvent> {
+    let aggressor = match trade["side"].as_str()? {
+        "BUY" => Side::Buy,
+        "SELL" => Side::Sell,
+        _ => return None,
+    };
+    Some(MarketEvent::Trade {
+        symbol: trade["product_id"].as_str()?.to_string(),
+        price: parse_number(&trade["price"])?,
+        quantity: parse_number(&trade["size"])?,
+        aggressor,
+    })
+}
+
+fn parse_level2(event: &Value, books: &mut BookSequencer) -> Option<MarketEvent> {
+    let symbol = event["product_id"].as_str()?.to_string();
+    let (mut bids, mut asks) = (Vec::new(), Vec::new());
+    for update in event["updates"].as_array()? {
+        let level = (parse_number(&update["price_level"])?, parse_number(&update["new_quantity"])?);
+        match update["side"].as_str()? {
+            "bid" => bids.push(level),
+            "offer" => asks.push(level),
+            _ => return None,
+        }
+    }
+    match event["type"].as_str()? {
+        "snapshot" => {
+            let snapshot = books.snapshot(&symbol, bids, asks);
+            Some(MarketEvent::BookSnapshot { symbol, snapshot })
+        }
+        "update" => {
+            let delta = books.delta(&symbol, bids, asks);
+            Some(MarketEvent::BookDelta { symbol, delta })
+        }
+        _ => None,
+    }
+}
+
+/// Coinbase encodes decimals as strings.
+fn parse_number(value: &Value) -> Option<f64> {
+    value.as_str()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/coinbase/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn parse(name: &str, books: &mut BookSequencer) -> Vec<TimedEvent> {
+        parse_message(&fixture(name), books)
+    }
+
+    #[test]
+    fn parses_ticker() {
+        let events = parse("ticker.json", &mut BookSequencer::default());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].exchange_time_ns, 1_675_974_637_167_359_596);
+        assert_eq!(events[0].event, MarketEvent::Ticker { symbol: "BTC-USD".into(), last_price: 21932.98 });
+    }
+
+    #[test]
+    fn parses_live_trades_with_taker_side() {
+        let events = parse("market_trades.json", &mut BookSequencer::default());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].exchange_time_ns, 1_675_973_975_382_000_000);
+        assert_eq!(
+            events[0].event,
+            MarketEvent::Trade { symbol: "BTC-USD".into(), price: 21921.74, quantity: 0.00104, aggressor: Side::Buy }
+        );
+        assert!(matches!(events[1].event, MarketEvent::Trade { aggressor: Side::Sell, .. }));
+    }
+
+    #[test]
+    fn skips_trade_snapshot_and_control_messages() {
+        let mut books = BookSequencer::default();
+        assert!(parse("market_trades_snapshot.json", &mut books).is_empty());
+        assert!(parse("subscriptions.json", &mut books).is_empty());
+        assert!(parse_message("not json", &mut books).is_empty());
+    }
+
+    #[test]
+    fn level2_deltas_chain_onto_snapshot() {
+        let mut books = BookSequencer::default();
+        let snapshot = parse("l2_snapshot.json", &mut books);
+        let update = parse("l2_update.json", &mut books);
+
+        let mut book = super::super::OrderBook::new();
+        match &snapshot[0].event {
+            MarketEvent::BookSnapshot { symbol, snapshot } => {
+                assert_eq!(symbol, "BTC-USD");
+                assert_eq!(snapshot.bids, vec![(21921.73, 0.06317902), (21921.3, 0.02)]);
+                assert_eq!(snapshot.asks, vec![(21921.74, 0.0375), (21922.5, 1.25)]);
+                book.apply_snapshot(snapshot.clone());
+            }
+            other => panic!("expected snapshot, got {:?}", other),
+        }
+        match &update[0].event {
+            MarketEvent::BookDelta { delta, .. } => {
+                assert_eq!(book.apply_delta(delta.clone()), super::super::DeltaOutcome::Applied);
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+        assert_eq!(book.best_bid(), Some((21921.3, 0.02)));
+        assert_eq!(book.best_ask(), Some((21921.74, 0.0375)));
+        assert_eq!(book.asks().nth(1), Some((21921.9, 0.5)));
+    }
+
+    #[test]
+    fn skipped_sequence_numbers_hold_every_book_for_resubscription() {
+        let mut books = BookSequencer::default();
+        let mut book = super::super::OrderBook::new();
+        for event in parse("l2_snapshot.json", &mut books) {
+            if let MarketEvent::BookSnapshot { snapshot, .. } = event.event {
+                book.apply_snapshot(snapshot);
+            }
+        }
+
+        // Sequence number 1 never arrived
+        let update = fixture("l2_update.json").replace("\"sequence_num\":1", "\"sequence_num\":2");
+        let events = parse_message(&update, &mut books);
+        assert_eq!(events.len(), 2);
+        match &events[0].event {
+            MarketEvent::BookDelta { symbol, delta } => {
+                assert_eq!(symbol, "BTC-USD");
+                assert_eq!(book.apply_delta(delta.clone()), super::super::DeltaOutcome::Gap);
+            }
+            other => panic!("expected gap delta, got {:?}", other),
+        }
+        assert!(!book.is_synced());
+        assert_eq!(books.take_resync(), vec!["BTC-USD".to_string()]);
+
+        // The resubscription snapshot restores the book
+        for event in parse("l2_snapshot.json", &mut books) {
+            if let MarketEvent::BookSnapshot { snapshot, .. } = event.event {
+                book.apply_snapshot(snapshot);
+            }
+        }
+        assert!(book.is_synced());
+        assert_eq!(book.best_bid(), Some((21921.73, 0.06317902)));
+    }
+}
@@ -0,0 +1,129 @@
+use super::order_book::OrderBook;
+use super::top_of_book::TopOfBook;
+
+/// Levels per side for `FairValue::Imbalance` when none are specified.
+pub const DEFAULT_IMBALANCE_LEVELS: usize = 5;
+
+/// How the reference price is derived from market data.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FairValue {
+    /// Last traded price from the ticker stream.
+    #[default]
+    LastPrice,
+    /// Midpoint of the best bid and ask.
+    Mid,
+    /// Best bid and ask weighted by the opposite side's size, so the price
+    /// leans toward the side more likely to be taken out next.
+    Microprice,
+    /// Mid shifted by the size imbalance of the top `levels` of each side,
+    /// scaled to the half spread. One level is the microprice.
+    Imbalance { levels: usize },
+}
+
+/// Fair value under `model`. Book-based models fall back to the last price
+/// while either side of the top of book is empty, and the imbalance model
+/// uses the top of book alone while the full book is not synchronized.
+pub fn estimate(model: FairValue, top: &TopOfBook, book: &OrderBook) -> f64 {
+    if model == FairValue::LastPrice || top.bid <= 0.0 || top.ask <= 0.0 {
+        return top.last_price;
+    }
+    let mid = (top.bid + top.ask) / 2.0;
+    let half_spread = (top.ask - top.bid) / 2.0;
+    let imbalance = match model {
+        FairValue::LastPrice | FairValue::Mid => return mid,
+        FairValue::Microprice => imbalance(top.bid_size, top.ask_size),
+        FairValue::Imbalance { levels } if book.is_synced() => {
+            imbalance(depth(book.bids(), levels), depth(book.asks(), levels))
+        }
+        FairValue::Imbalance { .. } => imbalance(top.bid_size, top.ask_size),
+    };
+    mid + imbalance * half_spread
+}
+
+/// Total size of the best `levels` levels (at least one) of a book side.
+fn depth(side: impl Iterator<Item = (f64, f64)>, levels: usize) -> f64 {
+    side.take(levels.max(1)).map(|(_, qty)| qty).sum()
+}
+
+/// `(bid - ask) / (bid + ask)` in `[-1, 1]`; 0 without size on either side.
+fn imbalance(bid_size: f64, ask_size: f64) -> f64 {
+    let total = bid_size + ask_size;
+    if total > 0.0 {
+        (bid_size - ask_size) / total
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_data::DepthSnapshot;
+
+    fn top(bid: (f64, f64), ask: (f64, f64)) -> TopOfBook {
+        TopOfBook { bid: bid.0, bid_size: bid.1, ask: ask.0, ask_size: ask.1, last_price: 100.7, ..TopOfBook::default() }
+    }
+
+    fn book(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBook {
+        let mut book = OrderBook::new();
+        book.apply_snapshot(DepthSnapshot { last_update_id: 1, bids: bids.to_vec(), asks: asks.to_vec() });
+        book
+    }
+
+    #[test]
+    fn defaults_to_the_last_price() {
+        let top = top((100.0, 1.0), (101.0, 3.0));
+        assert_eq!(FairValue::default(), FairValue::LastPrice);
+        assert_eq!(estimate(FairValue::default(), &top, &OrderBook::new()), 100.7);
+        assert_eq!(estimate(FairValue::Mid, &top, &OrderBook::new()), 100.5);
+    }
+
+    #[test]
+    fn microprice_leans_toward_the_thinner_side() {
+        let empty = OrderBook::new();
+        // Three times as much offered as bid leans toward the bid
+        assert_eq!(estimate(FairValue::Microprice, &top((100.0, 1.0), (101.0, 3.0)), &empty), 100.25);
+        assert_eq!(estimate(FairValue::Microprice, &top((100.0, 3.0), (101.0, 1.0)), &empty), 100.75);
+        assert_eq!(estimate(FairValue::Microprice, &top((100.0, 2.0), (101.0, 2.0)), &empty), 100.5);
+        // Without sizes there is no imbalance to lean on
+        assert_eq!(estimate(FairValue::Microprice, &top((100.0, 0.0), (101.0, 0.0)), &empty), 100.5);
+    }
+
+    #[test]
+    fn imbalance_sums_the_top_levels_of_each_side() {
+        let top = top((100.0, 1.0), (101.0, 1.0));
+        let book = book(&[(100.0, 1.0), (99.0, 5.0), (98.0, 100.0)], &[(101.0, 1.0), (102.0, 1.0), (103.0, 100.0)]);
+        // Two levels: 6 bid against 2 offered
+        assert_eq!(estimate(FairValue::Imbalance { levels: 2 }, &top, &book), 100.75);
+        // One level, or zero taken as one, is the microprice
+        assert_eq!(estimate(FairValue::Imbalance { levels: 1 }, &top, &book), 100.5);
+        assert_eq!(estimate(FairValue::Imbalance { levels: 0 }, &top, &book), 100.5);
+        // More levels than the book has use every level
+        assert_eq!(estimate(FairValue::Imbalance { levels: 10 }, &top, &book), 100.5 + 0.5 * (106.0 - 102.0) / 208.0);
+    }
+
+    #[test]
+    fn imbalance_uses_the_top_of_book_while_the_book_is_unsynced() {
+        let top = top((100.0, 3.0), (101.0, 1.0));
+        assert_eq!(estimate(FairValue::Imbalance { levels: 5 }, &top, &OrderBook::new()), 100.75);
+    }
+
+    #[test]
+    fn book_models_fall_back_to_the_last_price_without_both_sides() {
+        let one_sided = book(&[(100.0, 1.0)], &[]);
+        let empty = book(&[], &[]);
+        for model in [FairValue::Mid, FairValue::Microprice, FairValue::Imbalance { levels: 5 }] {
+            assert_eq!(estimate(model, &top((100.0, 1.0), (0.0, 0.0)), &one_sided), 100.7);
+            assert_eq!(estimate(model, &top((0.0, 0.0), (101.0, 1.0)), &one_sided), 100.7);
+            assert_eq!(estimate(model, &top((0.0, 0.0), (0.0, 0.0)), &empty), 100.7);
+        }
+    }
+
+    #[test]
+    fn imbalance_over_an_empty_book_side_leans_fully() {
+        // The top of book still shows both sides while the book has lost one
+        let top = top((100.0, 1.0), (101.0, 1.0));
+        assert_eq!(estimate(FairValue::Imbalance { levels: 3 }, &top, &book(&[(100.0, 1.0), (99.0, 1.0)], &[])), 101.0);
+        assert_eq!(estimate(FairValue::Imbalance { levels: 3 }, &top, &book(&[], &[])), 100.5);
+    }
+}
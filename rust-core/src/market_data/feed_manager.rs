@@ -0,0 +1,351 @@
+use super::{BinanceSource, FeedState, MarketDataFeed, MarketDataSource, MarketEvent, SnapshotProvider, TimedEvent};
+use super::order_book::DepthSnapshot;
+use super::health::Backoff;
+use super::latency::StreamKind;
+use super::source::FeedError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const FEED_WORKER_THREADS: usize = 2;
+
+/// Runtime shared by every feed task in the process.
+pub(super) fn shared_runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(FEED_WORKER_THREADS)
+            .thread_name("tradechest-feed")
+            .enable_all()
+            .build()
+            .expect("failed to build market data runtime")
+    })
+}
+
+/// Process-wide Binance feed manager used by `MarketDataFeed::start`.
+pub fn shared_binance_manager() -> &'static FeedManager {
+    static MANAGER: OnceLock<FeedManager> = OnceLock::new();
+    MANAGER.get_or_init(|| FeedManager::new(Box::new(BinanceSource::new())))
+}
+
+/// Routing entry owned by a connection task. A symbol can have several,
+/// one per feed state subscribed to it.
+pub(super) struct Route {
+    id: u64,
+    state: Arc<FeedState>,
+    snapshot_provider: Option<Arc<dyn SnapshotProvider>>,
+    last_snapshot_request: Option<Instant>,
+}
+
+impl Route {
+    fn new(state: Arc<FeedState>, snapshot_provider: Option<Arc<dyn SnapshotProvider>>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self { id, state, snapshot_provider, last_snapshot_request: None }
+    }
+}
+
+pub(super) enum Command {
+    Subscribe(String, Route),
+    /// Remove the route with this id from the symbol.
+    Unsubscribe(String, u64),
+}
+
+/// Keeps a feed state routed on a connection. Dropping it removes the
+/// route, and the symbol is unsubscribed once its last route is gone.
+pub(super) struct Subscription {
+    symbol: String,
+    route_id: u64,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Subscription {
+    /// Route events for `symbol` on the connection behind `commands` into
+    /// `state`.
+    pub(super) fn new(
+        commands: &mpsc::UnboundedSender<Command>,
+        symbol: &str,
+        state: Arc<FeedState>,
+        snapshot_provider: Option<Arc<dyn SnapshotProvider>>,
+    ) -> Self {
+        let symbol = symbol.to_uppercase();
+        let route = Route::new(state, snapshot_provider);
+        let route_id = route.id;
+        let _ = commands.send(Command::Subscribe(symbol.clone(), route));
+        Self { symbol, route_id, commands: commands.clone() }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Unsubscribe(std::mem::take(&mut self.symbol), self.route_id));
+    }
+}
+
+/// Multiplexes many symbols over a single source connection on the shared
+/// runtime and hands out a `MarketDataFeed` view per symbol.
+pub struct FeedManager {
+    commands: mpsc::UnboundedSender<Command>,
+    /// Feed handed out per symbol, kept until its last handle is dropped.
+    feeds: Mutex<HashMap<String, (MarketDataFeed, Weak<Subscription>)>>,
+    task: JoinHandle<()>,
+}
+
+impl FeedManager {
+    pub fn new(source: Box<dyn MarketDataSource>) -> Self {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let task = shared_runtime().spawn(run_connection(source, receiver));
+        Self { commands, feeds: Mutex::new(HashMap::new()), task }
+    }
+
+    /// Feed view for `symbol`, subscribing on the shared connection the
+    /// first time the symbol is requested. Every view of a symbol shares one
+    /// state, which is unsubscribed once the last of them is dropped.
+    pub fn subscribe(&self, symbol: &str) -> MarketDataFeed {
+        let key = symbol.to_uppercase();
+        let mut feeds = self.feeds.lock().unwrap();
+        if let Some(feed) = feeds.get(&key).and_then(live_view) {
+            return feed;
+        }
+        let mut feed = MarketDataFeed::new(key.clone());
+        let subscription = Arc::new(self.attach(&key, feed.state.clone(), feed.snapshot_provider.clone()));
+        feeds.insert(key, (feed.clone(), Arc::downgrade(&subscription)));
+        feed.subscription = Some(subscription);
+        feed
+    }
+
+    /// Route events for `symbol` into an existing feed state, alongside any
+    /// other states already routed for it, until the subscription is dropped.
+    pub(super) fn attach(
+        &self,
+        symbol: &str,
+        state: Arc<FeedState>,
+        snapshot_provider: Option<Arc<dyn SnapshotProvider>>,
+    ) -> Subscription {
+        Subscription::new(&self.commands, symbol, state, snapshot_provider)
+    }
+
+    /// Feed view for a symbol previously requested with `subscribe`, while
+    /// some view of it is still held.
+    pub fn feed(&self, symbol: &str) -> Option<MarketDataFeed> {
+        self.feeds.lock().unwrap().get(&symbol.to_uppercase()).and_then(live_view)
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        let feeds = self.feeds.lock().unwrap();
+        feeds.iter().filter(|(_, (_, subscription))| subscription.strong_count() > 0).map(|(symbol, _)| symbol.clone()).collect()
+    }
+}
+
+impl Drop for FeedManager {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Handle on a feed handed out by `FeedManager::subscribe`, if it is still
+/// subscribed.
+fn live_view((feed, subscription): &(MarketDataFeed, Weak<Subscription>)) -> Option<MarketDataFeed> {
+    let subscription = subscription.upgrade()?;
+    Some(MarketDataFeed { subscription: Some(subscription), ..feed.clone() })
+}
+
+/// Pump events from `source` to the feed states routed for each symbol,
+/// reconnecting with backoff on errors until the source reports that it is
+/// exhausted. Connection state is mirrored into each route's health.
+/// Routes are added and removed over `commands`, and the task ends once the
+/// channel is closed with no routes left. Order book snapshots are fetched
+/// in separate tasks so one symbol resyncing never stalls the others.
+pub(super) async fn run_connection(mut source: Box<dyn MarketDataSource>, commands: mpsc::UnboundedReceiver<Command>) {
+    let mut commands = Some(commands);
+    let mut routes: HashMap<String, Vec<Route>> = HashMap::new();
+    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel::<(String, u64, DepthSnapshot)>();
+    let mut backoff = Backoff::new();
+    loop {
+        // Nothing to connect for until the first symbol is requested
+        while routes.is_empty() {
+            let Some(receiver) = commands.as_mut() else { return };
+            match receiver.recv().await {
+                Some(command) => apply_command(source.as_mut(), &mut routes, command, false).await,
+                None => return,
+            }
+        }
+        // Fold in symbols requested together so they share the initial connect
+        if let Some(receiver) = commands.as_mut() {
+            while let Ok(command) = receiver.try_recv() {
+                apply_command(source.as_mut(), &mut routes, command, false).await;
+            }
+        }
+        if routes.is_empty() {
+            continue;
+        }
+
+        for route in routes.values().flatten() {
+            route.state.health.connecting();
+        }
+        if let Err(e) = connect(source.as_mut(), &routes).await {
+            log::warn!("feed connect failed: {}", e);
+            fail_all(&routes, &e);
+            tokio::time::sleep(backoff.next_delay()).await;
+            continue;
+        }
+        for route in routes.values().flatten() {
+            // Diffs from a previous connection cannot be bridged to the new one
+            route.state.reset_book();
+            route.state.health.connected();
+        }
+
+        let idle_timeout = source.idle_timeout();
+        let mut last_event = Instant::now();
+        loop {
+            tokio::select! {
+                command = recv_command(&mut commands) => match command {
+                    Some(command) => {
+                        apply_command(source.as_mut(), &mut routes, command, true).await;
+                        if routes.is_empty() {
+                            // Idle until a symbol is requested again
+                            break;
+                        }
+                    }
+                    None => commands = None,
+                },
+                Some((symbol, route_id, snapshot)) = snapshot_rx.recv() => {
+                    let route = routes.get(&symbol).and_then(|routes| routes.iter().find(|route| route.id == route_id));
+                    if let Some(route) = route {
+                        let event = MarketEvent::BookSnapshot { symbol, snapshot };
+                        route.state.health.message_received(StreamKind::Book);
+                        route.state.ingest(TimedEvent::received_now(0, event));
+                    }
+                }
+                event = source.next_event() => match event {
+                    Ok(Some(timed)) => {
+                        last_event = Instant::now();
+                        backoff.reset();
+                        if source.take_rewind() {
+                            for route in routes.values().flatten() {
+                                route.state.reset();
+                            }
+                        }
+                        let symbol = timed.event.symbol().to_uppercase();
+                        if let Some(symbol_routes) = routes.get_mut(&symbol) {
+                            for route in symbol_routes.iter_mut() {
+                                route.state.health.message_received(StreamKind::of(&timed.event));
+                                if route.state.ingest(timed.clone()) {
+                                    request_snapshot(&symbol, route, &snapshot_tx);
+                                }
+                            }
+                        }
+                    }
+                    Ok(None) => {
+                        for route in routes.values().flatten() {
+                            route.state.health.exhausted();
+                        }
+                        return;
+                    }
+                    Err(e) => {
+                        log::warn!("feed error: {}", e);
+                        fail_all(&routes, &e);
+                        break;
+                    }
+                },
+                _ = idle_deadline(idle_timeout, last_event) => {
+                    let e = FeedError::Idle(idle_timeout.unwrap_or_default());
+                    log::warn!("feed error: {}", e);
+                    fail_all(&routes, &e);
+                    break;
+                }
+            }
+        }
+        if !routes.is_empty() {
+            tokio::time::sleep(backoff.next_delay()).await;
+        }
+    }
+}
+
+/// Add or remove a route. The source is subscribed to a symbol when its
+/// first route arrives and unsubscribed when its last one leaves; `live`
+/// is whether the connection is up, so new routes can be marked connected.
+async fn apply_command(source: &mut dyn MarketDataSource, routes: &mut HashMap<String, Vec<Route>>, command: Command, live: bool) {
+    match command {
+        Command::Subscribe(symbol, route) => {
+            let symbol_routes = routes.entry(symbol.clone()).or_default();
+            let result = if symbol_routes.is_empty() { source.subscribe(&symbol).await } else { Ok(()) };
+            match result {
+                Ok(()) if live => route.state.health.connected(),
+                Ok(()) => {}
+                Err(e) => {
+                    log::warn!("{} subscribe failed: {}", symbol, e);
+                    route.state.health.failed(e.to_string());
+                }
+            }
+            symbol_routes.push(route);
+        }
+        Command::Unsubscribe(symbol, route_id) => {
+            let Some(symbol_routes) = routes.get_mut(&symbol) else { return };
+            symbol_routes.retain(|route| route.id != route_id);
+            if symbol_routes.is_empty() {
+                routes.remove(&symbol);
+                if let Err(e) = source.unsubscribe(&symbol).await {
+                    log::warn!("{} unsubscribe failed: {}", symbol, e);
+                }
+            }
+        }
+    }
+}
+
+fn fail_all(routes: &HashMap<String, Vec<Route>>, error: &FeedError) {
+    for route in routes.values().flatten() {
+        route.state.health.failed(error.to_string());
+    }
+}
+
+/// Completes once `idle_timeout` has passed since `last_event`; never
+/// completes without a timeout.
+async fn idle_deadline(idle_timeout: Option<Duration>, last_event: Instant) {
+    match idle_timeout {
+        Some(timeout) => tokio::time::sleep_until(last_event + timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn connect(source: &mut dyn MarketDataSource, routes: &HashMap<String, Vec<Route>>) -> Result<(), FeedError> {
+    for symbol in routes.keys() {
+        source.subscribe(symbol).await?;
+    }
+    source.connect().await
+}
+
+/// Next command, or pending forever once the channel is closed or absent.
+async fn recv_command(commands: &mut Option<mpsc::UnboundedReceiver<Command>>) -> Option<Command> {
+    match commands.as_mut() {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn request_snapshot(symbol: &str, route: &mut Route, snapshot_tx: &mpsc::UnboundedSender<(String, u64, DepthSnapshot)>) {
+    let provider = match &route.snapshot_provider {
+        Some(provider) => provider.clone(),
+        None => return,
+    };
+    if route.last_snapshot_request.is_some_and(|t| t.elapsed() < SNAPSHOT_RETRY_DELAY) {
+        return;
+    }
+    route.last_snapshot_request = Some(Instant::now());
+
+    let (symbol, route_id) = (symbol.to_string(), route.id);
+    let snapshot_tx = snapshot_tx.clone();
+    tokio::spawn(async move {
+        match provider.fetch(&symbol).await {
+            Ok(snapshot) => {
+                let _ = snapshot_tx.send((symbol, route_id, snapshot));
+            }
+            Err(e) => log::warn!("{} order book snapshot failed: {}", symbol, e),
+        }
+    });
+}
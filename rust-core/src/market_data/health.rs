@@ -0,0 +1,238 @@
+use super::latency::StreamKind;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// No book update for this long marks a connected feed as stale.
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(5);
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedStatus {
+    /// Establishing (or re-establishing) the connection.
+    Connecting = 0,
+    /// Connected and receiving book updates.
+    Live = 1,
+    /// Connected, but no depth or order book update within the stale
+    /// threshold, whatever other streams still deliver.
+    Stale = 2,
+    /// Not connected: never started, waiting to reconnect, or exhausted.
+    Down = 3,
+}
+
+/// Point-in-time view of a stream's health, as exposed over FFI.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedHealthSnapshot {
+    pub status: FeedStatus,
+    /// Milliseconds since the last message on any stream, or `u64::MAX` if
+    /// none yet.
+    pub last_message_age_ms: u64,
+    /// Successful connections after the first one.
+    pub reconnect_count: u64,
+    pub error_count: u64,
+}
+
+#[derive(Debug)]
+struct HealthState {
+    connected: bool,
+    ever_connected: bool,
+    down: bool,
+    /// When the current connection came up; streams are aged from here
+    /// until their first message on it.
+    connected_at: Option<Instant>,
+    /// Last message per `StreamKind`.
+    last_messages: [Option<Instant>; StreamKind::ALL.len()],
+    reconnect_count: u64,
+    error_count: u64,
+    last_error: Option<String>,
+}
+
+/// Connection and per-stream message-flow tracking for one symbol. Quoting
+/// depends on the book, so the feed is stale once the depth and order book
+/// streams go quiet even while tickers or trades keep arriving.
+#[derive(Debug)]
+pub struct FeedHealth {
+    stale_after: Mutex<Duration>,
+    state: Mutex<HealthState>,
+}
+
+impl Default for FeedHealth {
+    fn default() -> Self {
+        Self {
+            stale_after: Mutex::new(DEFAULT_STALE_AFTER),
+            state: Mutex::new(HealthState {
+                connected: false,
+                ever_connected: false,
+                down: true,
+                connected_at: None,
+                last_messages: [None; StreamKind::ALL.len()],
+                reconnect_count: 0,
+                error_count: 0,
+                last_error: None,
+            }),
+        }
+    }
+}
+
+impl FeedHealth {
+    pub fn set_stale_after(&self, stale_after: Duration) {
+        *self.stale_after.lock().unwrap() = stale_after;
+    }
+
+    pub(super) fn connecting(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connected = false;
+        state.down = false;
+    }
+
+    pub(super) fn connected(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.ever_connected {
+            state.reconnect_count += 1;
+        }
+        state.ever_connected = true;
+        state.connected = true;
+        state.down = false;
+        state.connected_at = Some(Instant::now());
+    }
+
+    pub(super) fn message_received(&self, stream: StreamKind) {
+        self.state.lock().unwrap().last_messages[stream as usize] = Some(Instant::now());
+    }
+
+    /// Record a failure that took the stream down.
+    pub(super) fn failed(&self, reason: String) {
+        let mut state = self.state.lock().unwrap();
+        state.connected = false;
+        state.down = true;
+        state.error_count += 1;
+        state.last_error = Some(reason);
+    }
+
+    /// The source ended normally (for example a replay reached its end).
+    pub(super) fn exhausted(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connected = false;
+        state.down = true;
+    }
+
+    pub fn status(&self) -> FeedStatus {
+        self.snapshot().status
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.status() == FeedStatus::Live
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.state.lock().unwrap().last_error.clone()
+    }
+
+    /// Time since the last message on `stream`, `None` if it never had one.
+    pub fn stream_age(&self, stream: StreamKind) -> Option<Duration> {
+        self.state.lock().unwrap().last_messages[stream as usize].map(|t| t.elapsed())
+    }
+
+    pub fn snapshot(&self) -> FeedHealthSnapshot {
+        let stale_after = *self.stale_after.lock().unwrap();
+        let state = self.state.lock().unwrap();
+        // Ages run from the connection until the first message arrives
+        let since = |streams: &[StreamKind]| {
+            let last = streams.iter().filter_map(|&stream| state.last_messages[stream as usize]).max();
+            last.max(state.connected_at).map(|t| t.elapsed())
+        };
+        let age = since(&StreamKind::ALL);
+        let book_age = since(&[StreamKind::Depth, StreamKind::Book]);
+        let status = if state.down {
+            FeedStatus::Down
+        } else if !state.connected {
+            FeedStatus::Connecting
+        } else if book_age.is_some_and(|age| age > stale_after) {
+            FeedStatus::Stale
+        } else {
+            FeedStatus::Live
+        };
+        FeedHealthSnapshot {
+            status,
+            last_message_age_ms: age.map_or(u64::MAX, |age| age.as_millis() as u64),
+            reconnect_count: state.reconnect_count,
+            error_count: state.error_count,
+        }
+    }
+}
+
+/// Exponential reconnect backoff with jitter: each delay is drawn uniformly
+/// from the upper half of a ceiling that doubles per failure, so many
+/// clients dropped together do not reconnect in lockstep.
+#[derive(Debug)]
+pub(super) struct Backoff {
+    ceiling: Duration,
+    rng: fastrand::Rng,
+}
+
+impl Backoff {
+    pub(super) fn new() -> Self {
+        Self { ceiling: INITIAL_BACKOFF, rng: fastrand::Rng::new() }
+    }
+
+    pub(super) fn next_delay(&mut self) -> Duration {
+        let ceiling = self.ceiling;
+        self.ceiling = (ceiling * 2).min(MAX_BACKOFF);
+        ceiling.mul_f64(0.5 + 0.5 * self.rng.f64())
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.ceiling = INITIAL_BACKOFF;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_stale_when_the_book_streams_stop_while_others_continue() {
+        let health = FeedHealth::default();
+        health.set_stale_after(Duration::from_millis(50));
+        assert_eq!(health.status(), FeedStatus::Down);
+        health.connecting();
+        assert_eq!(health.status(), FeedStatus::Connecting);
+        health.connected();
+        assert!(health.is_live());
+        assert_eq!(health.stream_age(StreamKind::Book), None);
+
+        health.message_received(StreamKind::Book);
+        std::thread::sleep(Duration::from_millis(80));
+        health.message_received(StreamKind::Ticker);
+        health.message_received(StreamKind::Trade);
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.status, FeedStatus::Stale);
+        assert!(snapshot.last_message_age_ms < 50);
+        assert!(health.stream_age(StreamKind::Book).unwrap() >= Duration::from_millis(80));
+
+        // Either book stream keeps the feed live
+        health.message_received(StreamKind::Depth);
+        assert!(health.is_live());
+    }
+
+    #[test]
+    fn ages_book_streams_from_the_connection_until_they_deliver() {
+        let health = FeedHealth::default();
+        health.set_stale_after(Duration::from_millis(50));
+        health.connected();
+        health.message_received(StreamKind::Ticker);
+        assert!(health.is_live());
+        std::thread::sleep(Duration::from_millis(80));
+        health.message_received(StreamKind::Ticker);
+        assert_eq!(health.status(), FeedStatus::Stale);
+
+        health.failed("closed".into());
+        assert_eq!(health.status(), FeedStatus::Down);
+        health.connected();
+        assert!(health.is_live());
+        assert_eq!(health.snapshot().reconnect_count, 1);
+    }
+}
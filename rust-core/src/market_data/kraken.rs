@@ -0,0 +1,477 @@
+use super::order_book::{set_levels, BookSequencer, Price};
+use super::source::{parse_rfc3339_ns, FeedError, MarketDataSource, MarketEvent, Side, TimedEvent};
+use super::ws::{self, WsStream};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+pub const KRAKEN_STREAM_URL: &str = "wss://ws.kraken.com/v2";
+
+/// Kraken sends a heartbeat every second on an otherwise idle connection.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Levels per side requested on the `book` channel. Kraken does not send
+/// deletes for levels pushed beyond this depth; they are deleted locally.
+const BOOK_DEPTH: usize = 100;
+
+/// Levels per side covered by the book checksum.
+const CHECKSUM_DEPTH: usize = 10;
+
+/// Kraken WebSocket v2 public market data (`ticker`, `trade` and `book`
+/// channels) for any number of pairs over one connection. Symbols are
+/// Kraken v2 pairs such as `BTC/USD`.
+///
+/// Each book message carries a checksum of the venue's book after it. A
+/// mismatch means the local book has diverged, so it is held until the
+/// pair's `book` channel is resubscribed and sends a fresh snapshot.
+pub struct KrakenSource {
+    base_url: String,
+    stream: Option<WsStream>,
+    symbols: Vec<String>,
+    books: Books,
+    pending: VecDeque<TimedEvent>,
+    next_request_id: u64,
+}
+
+impl KrakenSource {
+    pub fn new() -> Self {
+        Self::with_base_url(KRAKEN_STREAM_URL)
+    }
+
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            stream: None,
+            symbols: Vec::new(),
+            books: Books::default(),
+            pending: VecDeque::new(),
+            next_request_id: 1,
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        json!({ "method": method, "params": params, "req_id": id })
+    }
+
+    fn subscribe_requests(&mut self, symbols: &[String]) -> Vec<Value> {
+        let params = [
+            json!({ "channel": "ticker", "symbol": symbols }),
+            // Trade history on subscribe would be counted as live flow
+            json!({ "channel": "trade", "symbol": symbols, "snapshot": false }),
+            json!({ "channel": "book", "symbol": symbols, "depth": BOOK_DEPTH }),
+        ];
+        params.into_iter().map(|params| self.request("subscribe", params)).collect()
+    }
+
+    fn unsubscribe_requests(&mut self, symbols: &[String]) -> Vec<Value> {
+        let params = [
+            json!({ "channel": "ticker", "symbol": symbols }),
+            json!({ "channel": "trade", "symbol": symbols }),
+            json!({ "channel": "book", "symbol": symbols, "depth": BOOK_DEPTH }),
+        ];
+        params.into_iter().map(|params| self.request("unsubscribe", params)).collect()
+    }
+
+    /// Resubscribe the `book` channel for pairs whose checksum failed, which
+    /// makes Kraken send a new snapshot of each.
+    async fn resync_books(&mut self) -> Result<(), FeedError> {
+        let symbols = self.books.sequencer.take_resync();
+        if symbols.is_empty() {
+            return Ok(());
+        }
+        log::warn!("Kraken book checksum mismatch, resubscribing {}", symbols.join(", "));
+        let params = json!({ "channel": "book", "symbol": symbols, "depth": BOOK_DEPTH });
+        let requests = [self.request("unsubscribe", params.clone()), self.request("subscribe", params)];
+        if let Some(stream) = self.stream.as_mut() {
+            for request in requests {
+                ws::send_json(stream, &request).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for KrakenSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketDataSource for KrakenSource {
+    /// Connect and subscribe every pair added so far.
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>> {
+        Box::pin(async move {
+            self.pending.clear();
+            self.books.reconnected();
+            let mut stream = ws::connect(&self.base_url).await?;
+            // Pair precisions, which the book checksums are computed at
+            let instrument = self.request("subscribe", json!({ "channel": "instrument" }));
+            ws::send_json(&mut stream, &instrument).await?;
+            let symbols = self.symbols.clone();
+            for request in self.subscribe_requests(&symbols) {
+                ws::send_json(&mut stream, &request).await?;
+            }
+            self.stream = Some(stream);
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_uppercase();
+            if self.symbols.contains(&symbol) {
+                return Ok(());
+            }
+            self.symbols.push(symbol.clone());
+            if self.stream.is_some() {
+                let requests = self.subscribe_requests(&[symbol]);
+                if let Some(stream) = self.stream.as_mut() {
+                    for request in requests {
+                        ws::send_json(stream, &request).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn unsubscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            let symbol = symbol.to_uppercase();
+            let Some(index) = self.symbols.iter().position(|s| *s == symbol) else {
+                return Ok(());
+            };
+            self.symbols.remove(index);
+            if self.stream.is_some() {
+                let requests = self.unsubscribe_requests(&[symbol]);
+                if let Some(stream) = self.stream.as_mut() {
+                    for request in requests {
+                        ws::send_json(stream, &request).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>> {
+        Box::pin(async move {
+            while self.pending.is_empty() {
+                let text = ws::next_text(&mut self.stream).await?;
+                self.pending.extend(parse_message(&text, &mut self.books));
+                self.resync_books().await?;
+            }
+            Ok(self.pending.pop_front())
+        })
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(IDLE_TIMEOUT)
+    }
+}
+
+/// Book state of one connection.
+#[derive(Debug, Default)]
+struct Books {
+    sequencer: BookSequencer,
+    /// Each pair's book at the subscribed depth, absent while the pair is
+    /// being resubscribed.
+    depth: HashMap<String, DepthBook>,
+    precisions: HashMap<String, Precision>,
+}
+
+impl Books {
+    fn reconnected(&mut self) {
+        self.sequencer.reconnected();
+        self.depth.clear();
+    }
+}
+
+/// Decimal places of a pair's prices and quantities, from the `instrument`
+/// channel.
+#[derive(Debug, Clone, Copy)]
+struct Precision {
+    price: usize,
+    qty: usize,
+}
+
+/// One pair's book as Kraken keeps it, truncated to the subscribed depth.
+#[derive(Debug, Default)]
+struct DepthBook {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl DepthBook {
+    /// Apply levels, then drop the levels pushed beyond `BOOK_DEPTH`,
+    /// appending them to `bids` and `asks` as deletions.
+    fn apply(&mut self, bids: &mut Vec<(f64, f64)>, asks: &mut Vec<(f64, f64)>) {
+        set_levels(&mut self.bids, bids);
+        set_levels(&mut self.asks, asks);
+        let beyond_bids: Vec<Price> = self.bids.keys().rev().skip(BOOK_DEPTH).copied().collect();
+        let beyond_asks: Vec<Price> = self.asks.keys().skip(BOOK_DEPTH).copied().collect();
+        delete(&mut self.bids, beyond_bids, bids);
+        delete(&mut self.asks, beyond_asks, asks);
+    }
+
+    /// CRC32 of the top asks then bids, best first, each price and quantity
+    /// printed at the pair's precision without the decimal point or leading
+    /// zeros.
+    fn checksum(&self, precision: Precision) -> u32 {
+        let mut text = String::new();
+        let asks = self.asks.iter().take(CHECKSUM_DEPTH);
+        for (price, qty) in asks.chain(self.bids.iter().rev().take(CHECKSUM_DEPTH)) {
+            for (value, decimals) in [(price.0, precision.price), (*qty, precision.qty)] {
+                let digits = format!("{:.*}", decimals, value).replace('.', "");
+                text.push_str(digits.trim_start_matches('0'));
+            }
+        }
+        let mut crc = flate2::Crc::new();
+        crc.update(text.as_bytes());
+        crc.sum()
+    }
+}
+
+/// Parse one channel message into normalized events. Method responses,
+/// heartbeats, status updates, instrument updates and unknown channels
+/// yield nothing.
+fn parse_message(text: &str, books: &mut Books) -> Vec<TimedEvent> {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(_) => return Vec::new(),
+    };
+    if message["channel"].as_str() == Some("instrument") {
+        for pair in message["data"]["pairs"].as_array().into_iter().flatten() {
+            if let (Some(symbol), Some(price), Some(qty)) =
+                (pair["symbol"].as_str(), pair["price_precision"].as_u64(), pair["qty_precision"].as_u64())
+            {
+                books.precisions.insert(symbol.to_string(), Precision { price: price as usize, qty: qty as usize });
+            }
+        }
+        return Vec::new();
+    }
+    let entries = match message["data"].as_array() {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+    let kind = message["type"].as_str();
+
+    let mut parsed = Vec::new();
+    for entry in entries {
+        // Tickers and book snapshots carry no exchange timestamp
+        let exchange_time_ns = entry["timestamp"].as_str().and_then(parse_rfc3339_ns).unwrap_or(0);
+        let event = match message["channel"].as_str() {
+            Some("ticker") => parse_ticker(entry),
+            Some("trade") => parse_trade(entry),
+            Some("book") => kind.and_then(|kind| parse_book(kind, entry, books)),
+            _ => None,
+        };
+        if let Some(event) = event {
+            parsed.push(TimedEvent::received_now(exchange_time_ns, event));
+        }
+    }
+    parsed
+}
+
+fn parse_ticker(ticker: &Value) -> Option<MarketEvent> {
+    Some(MarketEvent::Ticker {
+        symbol: ticker["symbol"].as_str()?.to_string(),
+        last_price: ticker["last"].as_f64()?,
+    })
+}
+
+/// `side` is the taker side of the trade.
+fn parse_trade(trade: &Value) -> Option<MarketEvent> {
+    let aggressor = match trade["side"].as_str()? {
+        "buy" => Side::Buy,
+        "sell" => Side::Sell,
+        _ => return None,
+    };
+    Some(MarketEvent::Trade {
+        symbol: trade["symbol"].as_str()?.to_string(),
+        price: trade["price"].as_f64()?,
+        quantity: trade["qty"].as_f64()?,
+        aggressor,
+    })
+}
+
+/// Levels Kraken pushes beyond the subscribed depth are deleted from the
+/// event. Once the pair's precisions are known, a checksum mismatch turns
+/// the message into a gap delta and queues the pair for resubscription.
+fn parse_book(kind: &str, book: &Value, books: &mut Books) -> Option<MarketEvent> {
+    let symbol = book["symbol"].as_str()?.to_string();
+    let mut bids = parse_levels(&book["bids"])?;
+    let mut asks = parse_levels(&book["asks"])?;
+    match kind {
+        "snapshot" => {
+            books.depth.insert(symbol.clone(), DepthBook::default());
+        }
+        "update" => {}
+        _ => return None,
+    }
+
+    // Updates for a pair being resubscribed are stale against its next snapshot
+    if let Some(depth_book) = books.depth.get_mut(&symbol) {
+        depth_book.apply(&mut bids, &mut asks);
+        let diverged = match (book["checksum"].as_u64(), books.precisions.get(&symbol)) {
+            (Some(checksum), Some(&precision)) => u64::from(depth_book.checksum(precision)) != checksum,
+            _ => false,
+        };
+        if diverged {
+            books.depth.remove(&symbol);
+            let delta = books.sequencer.gap(&symbol);
+            return Some(MarketEvent::BookDelta { symbol, delta });
+        }
+    }
+
+    if kind == "snapshot" {
+        let snapshot = books.sequencer.snapshot(&symbol, bids, asks);
+        Some(MarketEvent::BookSnapshot { symbol, snapshot })
+    } else {
+        let delta = books.sequencer.delta(&symbol, bids, asks);
+        Some(MarketEvent::BookDelta { symbol, delta })
+    }
+}
+
+fn delete(side: &mut BTreeMap<Price, f64>, prices: Vec<Price>, deletions: &mut Vec<(f64, f64)>) {
+    for price in prices {
+        side.remove(&price);
+        deletions.push((price.0, 0.0));
+    }
+}
+
+fn parse_levels(levels: &Value) -> Option<Vec<(f64, f64)>> {
+    levels
+        .as_array()?
+        .iter()
+        .map(|level| Some((level["price"].as_f64()?, level["qty"].as_f64()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/kraken/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn parse(name: &str, books: &mut Books) -> Vec<TimedEvent> {
+        parse_message(&fixture(name), books)
+    }
+
+    #[test]
+    fn parses_ticker() {
+        let events = parse("ticker.json", &mut Books::default());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].exchange_time_ns, 0);
+        assert_eq!(events[0].event, MarketEvent::Ticker { symbol: "BTC/USD".into(), last_price: 26930.1 });
+    }
+
+    #[test]
+    fn parses_trades_with_taker_side() {
+        let events = parse("trade.json", &mut Books::default());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].exchange_time_ns, 1_695_628_116_925_533_000);
+        assert_eq!(
+            events[0].event,
+            MarketEvent::Trade { symbol: "BTC/USD".into(), price: 26929.9, quantity: 0.00466, aggressor: Side::Sell }
+        );
+        assert!(matches!(events[1].event, MarketEvent::Trade { aggressor: Side::Buy, .. }));
+    }
+
+    #[test]
+    fn skips_control_messages() {
+        let mut books = Books::default();
+        assert!(parse("heartbeat.json", &mut books).is_empty());
+        assert!(parse("subscribe_ack.json", &mut books).is_empty());
+        assert!(parse_message("{\"channel\":\"book\"", &mut books).is_empty());
+    }
+
+    #[test]
+    fn book_updates_chain_onto_snapshot() {
+        let mut books = Books::default();
+        assert!(parse("instrument.json", &mut books).is_empty());
+        let snapshot = parse("book_snapshot.json", &mut books);
+        let update = parse("book_update.json", &mut books);
+        assert_eq!(update[0].exchange_time_ns, 1_696_613_755_440_295_000);
+
+        let mut book = super::super::OrderBook::new();
+        match &snapshot[0].event {
+            MarketEvent::BookSnapshot { symbol, snapshot } => {
+                assert_eq!(symbol, "BTC/USD");
+                assert_eq!(snapshot.bids.len(), 3);
+                assert_eq!(snapshot.asks.len(), 2);
+                book.apply_snapshot(snapshot.clone());
+            }
+            other => panic!("expected snapshot, got {:?}", other),
+        }
+        match &update[0].event {
+            MarketEvent::BookDelta { delta, .. } => {
+                assert_eq!(book.apply_delta(delta.clone()), super::super::DeltaOutcome::Applied);
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+        assert_eq!(book.best_bid(), Some((26929.8, 0.4)));
+        assert_eq!(book.best_ask(), Some((26930.1, 2.05)));
+        assert_eq!(book.depth(), (3, 2));
+    }
+
+    #[test]
+    fn checksum_mismatch_holds_the_book_for_resubscription() {
+        let mut books = Books::default();
+        parse("instrument.json", &mut books);
+        let mut book = super::super::OrderBook::new();
+        if let MarketEvent::BookSnapshot { snapshot, .. } = &parse("book_snapshot.json", &mut books)[0].event {
+            book.apply_snapshot(snapshot.clone());
+        }
+
+        let update = fixture("book_update.json").replace("1574997397", "1574997398");
+        let events = parse_message(&update, &mut books);
+        match &events[0].event {
+            MarketEvent::BookDelta { delta, .. } => {
+                assert!(delta.bids.is_empty() && delta.asks.is_empty());
+                assert_eq!(book.apply_delta(delta.clone()), super::super::DeltaOutcome::Gap);
+            }
+            other => panic!("expected gap delta, got {:?}", other),
+        }
+        assert_eq!(books.sequencer.take_resync(), vec!["BTC/USD".to_string()]);
+
+        // Updates before the new snapshot are not checked against a book Kraken no longer sends
+        assert_eq!(parse_message(&update, &mut books).len(), 1);
+        assert!(books.sequencer.take_resync().is_empty());
+        if let MarketEvent::BookSnapshot { snapshot, .. } = &parse("book_snapshot.json", &mut books)[0].event {
+            book.apply_snapshot(snapshot.clone());
+        }
+        assert!(book.is_synced());
+        assert_eq!(book.best_bid(), Some((26930.0, 0.71)));
+    }
+
+    #[test]
+    fn deletes_levels_pushed_beyond_the_subscribed_depth() {
+        let levels = |start: f64, step: f64| -> Vec<Value> {
+            (0..BOOK_DEPTH).map(|i| json!({ "price": start + step * i as f64, "qty": 1.0 })).collect()
+        };
+        let message = |kind: &str, bids: Vec<Value>, asks: Vec<Value>| {
+            json!({ "channel": "book", "type": kind, "data": [{ "symbol": "BTC/USD", "bids": bids, "asks": asks }] })
+                .to_string()
+        };
+        let mut books = Books::default();
+        parse_message(&message("snapshot", levels(1000.0, -1.0), levels(1001.0, 1.0)), &mut books);
+
+        let better = vec![json!({ "price": 1000.5, "qty": 2.0 })];
+        let events = parse_message(&message("update", better.clone(), better), &mut books);
+        match &events[0].event {
+            MarketEvent::BookDelta { delta, .. } => {
+                // Each side is full, so its worst level is pushed out
+                assert_eq!(delta.bids, vec![(1000.5, 2.0), (1000.0 - (BOOK_DEPTH - 1) as f64, 0.0)]);
+                assert_eq!(delta.asks, vec![(1000.5, 2.0), (1001.0 + (BOOK_DEPTH - 1) as f64, 0.0)]);
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use super::source::MarketEvent;
+use super::time_series::TimeSeries;
+
+/// Latency samples kept per stream.
+pub const LATENCY_SAMPLES: usize = 1024;
+
+/// Market data stream an event arrived on.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Ticker = 0,
+    Depth = 1,
+    /// Order book snapshots and deltas.
+    Book = 2,
+    Trade = 3,
+}
+
+impl StreamKind {
+    pub const ALL: [StreamKind; 4] = [StreamKind::Ticker, StreamKind::Depth, StreamKind::Book, StreamKind::Trade];
+
+    pub fn of(event: &MarketEvent) -> Self {
+        match event {
+            MarketEvent::Ticker { .. } => StreamKind::Ticker,
+            MarketEvent::Depth { .. } => StreamKind::Depth,
+            MarketEvent::BookSnapshot { .. } | MarketEvent::BookDelta { .. } => StreamKind::Book,
+            MarketEvent::Trade { .. } => StreamKind::Trade,
+        }
+    }
+
+    pub fn from_index(index: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+/// Distribution of receive time minus exchange event time over the recent
+/// samples of one stream. Negative values mean the local clock is behind
+/// the exchange's. All zero without samples.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub sample_count: u64,
+    pub p50_ns: i64,
+    pub p99_ns: i64,
+    pub min_ns: i64,
+    pub max_ns: i64,
+}
+
+/// Per-stream feed latency, measured as local receive time minus exchange
+/// event time, for events that carry an exchange timestamp.
+#[derive(Debug)]
+pub struct FeedLatency {
+    streams: [TimeSeries<i64>; StreamKind::ALL.len()],
+}
+
+impl Default for FeedLatency {
+    fn default() -> Self {
+        Self { streams: std::array::from_fn(|_| TimeSeries::with_capacity(LATENCY_SAMPLES)) }
+    }
+}
+
+impl FeedLatency {
+    pub fn record(&mut self, stream: StreamKind, exchange_time_ns: u64, receive_time_ns: u64) {
+        if exchange_time_ns == 0 {
+            return;
+        }
+        let latency_ns = receive_time_ns as i64 - exchange_time_ns as i64;
+        self.streams[stream as usize].push(receive_time_ns, latency_ns);
+    }
+
+    pub fn stats(&self, stream: StreamKind) -> LatencyStats {
+        let mut samples: Vec<i64> = self.streams[stream as usize].iter().map(|(_, latency)| latency).collect();
+        if samples.is_empty() {
+            return LatencyStats::default();
+        }
+        samples.sort_unstable();
+        LatencyStats {
+            sample_count: samples.len() as u64,
+            p50_ns: percentile(&samples, 0.50),
+            p99_ns: percentile(&samples, 0.99),
+            min_ns: samples[0],
+            max_ns: samples[samples.len() - 1],
+        }
+    }
+
+    /// Local clock minus exchange clock, in nanoseconds. Every sample is the
+    /// offset plus a non-negative network delay, so the smallest recent
+    /// sample across all streams bounds the offset from above, and is
+    /// close to it whenever some message arrived with little queueing.
+    pub fn clock_offset_ns(&self) -> Option<i64> {
+        self.streams.iter().flat_map(|samples| samples.iter().map(|(_, latency)| latency)).min()
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`.
+fn percentile(samples: &[i64], quantile: f64) -> i64 {
+    let rank = (quantile * samples.len() as f64).ceil() as usize;
+    samples[rank.clamp(1, samples.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let samples: Vec<i64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 0.50), 50);
+        assert_eq!(percentile(&samples, 0.99), 99);
+        assert_eq!(percentile(&samples, 1.0), 100);
+        assert_eq!(percentile(&samples, 0.0), 1);
+        assert_eq!(percentile(&[7], 0.50), 7);
+        assert_eq!(percentile(&[7], 0.99), 7);
+    }
+
+    #[test]
+    fn stats_over_recorded_samples() {
+        let mut latency = FeedLatency::default();
+        assert_eq!(latency.stats(StreamKind::Trade), LatencyStats::default());
+
+        latency.record(StreamKind::Trade, 1_000, 1_250);
+        assert_eq!(
+            latency.stats(StreamKind::Trade),
+            LatencyStats { sample_count: 1, p50_ns: 250, p99_ns: 250, min_ns: 250, max_ns: 250 }
+        );
+
+        // Latencies 100 down to 1, recorded out of order
+        let mut latency = FeedLatency::default();
+        for i in 0..100u64 {
+            let receive_ns = 1_000_000 + i * 1_000;
+            latency.record(StreamKind::Depth, receive_ns - (100 - i), receive_ns);
+        }
+        assert_eq!(
+            latency.stats(StreamKind::Depth),
+            LatencyStats { sample_count: 100, p50_ns: 50, p99_ns: 99, min_ns: 1, max_ns: 100 }
+        );
+        assert_eq!(latency.stats(StreamKind::Ticker), LatencyStats::default());
+    }
+
+    #[test]
+    fn ignores_events_without_an_exchange_time() {
+        let mut latency = FeedLatency::default();
+        latency.record(StreamKind::Ticker, 0, 5_000);
+        assert_eq!(latency.stats(StreamKind::Ticker).sample_count, 0);
+        assert_eq!(latency.clock_offset_ns(), None);
+    }
+
+    #[test]
+    fn clock_offset_is_the_smallest_sample_across_streams() {
+        let mut latency = FeedLatency::default();
+        latency.record(StreamKind::Ticker, 10_000, 10_400);
+        latency.record(StreamKind::Book, 20_000, 20_150);
+        latency.record(StreamKind::Trade, 30_000, 30_900);
+        assert_eq!(latency.clock_offset_ns(), Some(150));
+
+        // A local clock behind the exchange's gives a negative offset
+        latency.record(StreamKind::Trade, 40_000, 39_700);
+        assert_eq!(latency.clock_offset_ns(), Some(-300));
+    }
+}
@@ -0,0 +1,108 @@
+use super::order_book::OrderBook;
+use super::top_of_book::TopOfBook;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidityConfig {
+    /// Depth is counted on both sides within this distance of the mid.
+    pub band_bps: f64,
+    /// Depth per side (in base units) that counts as normal liquidity,
+    /// i.e. a factor of 1.
+    pub reference_depth: f64,
+    pub min_factor: f64,
+    pub max_factor: f64,
+}
+
+impl Default for LiquidityConfig {
+    fn default() -> Self {
+        Self { band_bps: 10.0, reference_depth: 10.0, min_factor: 0.5, max_factor: 2.0 }
+    }
+}
+
+/// Resting size within the configured band around the mid, averaged over
+/// both sides and relative to the reference depth: above 1 the market is
+/// deeper than normal. Uses the top of book alone while the full book is not
+/// synchronized, and returns 1 without a two-sided quote.
+pub fn estimate(top: &TopOfBook, book: &OrderBook, config: &LiquidityConfig) -> f64 {
+    if top.bid <= 0.0 || top.ask <= 0.0 || config.reference_depth <= 0.0 {
+        return 1.0;
+    }
+    let mid = (top.bid + top.ask) / 2.0;
+    let band = mid * config.band_bps / 10_000.0;
+    let (floor, ceiling) = (mid - band, mid + band);
+
+    let (bid_depth, ask_depth) = if book.is_synced() {
+        (
+            depth(book.bids().take_while(|&(price, _)| price >= floor)),
+            depth(book.asks().take_while(|&(price, _)| price <= ceiling)),
+        )
+    } else {
+        (
+            if top.bid >= floor { top.bid_size } else { 0.0 },
+            if top.ask <= ceiling { top.ask_size } else { 0.0 },
+        )
+    };
+    let per_side = (bid_depth + ask_depth) / 2.0;
+    (per_side / config.reference_depth).clamp(config.min_factor, config.max_factor)
+}
+
+fn depth(levels: impl Iterator<Item = (f64, f64)>) -> f64 {
+    levels.map(|(_, qty)| qty).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_data::DepthSnapshot;
+
+    /// Unclamped config with a reference depth of 4 per side.
+    fn config() -> LiquidityConfig {
+        LiquidityConfig { band_bps: 10.0, reference_depth: 4.0, min_factor: 0.0, max_factor: 100.0 }
+    }
+
+    fn top(bid: (f64, f64), ask: (f64, f64)) -> TopOfBook {
+        TopOfBook { bid: bid.0, bid_size: bid.1, ask: ask.0, ask_size: ask.1, ..TopOfBook::default() }
+    }
+
+    fn book(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBook {
+        let mut book = OrderBook::new();
+        book.apply_snapshot(DepthSnapshot { last_update_id: 1, bids: bids.to_vec(), asks: asks.to_vec() });
+        book
+    }
+
+    #[test]
+    fn sums_depth_inside_the_band() {
+        // Mid 100 and a 10 bps band: levels from 99.9 to 100.1 count
+        let top = top((99.95, 2.0), (100.05, 1.0));
+        let book = book(
+            &[(99.95, 2.0), (99.9, 3.0), (99.8, 50.0)],
+            &[(100.05, 1.0), (100.08, 4.0), (100.3, 50.0)],
+        );
+        assert_eq!(estimate(&top, &book, &config()), 1.25);
+    }
+
+    #[test]
+    fn uses_the_top_of_book_while_unsynced() {
+        let top = top((99.95, 2.0), (100.05, 6.0));
+        assert_eq!(estimate(&top, &OrderBook::new(), &config()), 1.0);
+
+        // A best price outside the band adds nothing
+        let narrow = LiquidityConfig { band_bps: 1.0, ..config() };
+        assert_eq!(estimate(&top, &OrderBook::new(), &narrow), 0.0);
+    }
+
+    #[test]
+    fn returns_one_without_a_two_sided_quote() {
+        let book = book(&[(99.95, 2.0)], &[]);
+        assert_eq!(estimate(&top((99.95, 2.0), (0.0, 0.0)), &book, &config()), 1.0);
+        assert_eq!(estimate(&top((0.0, 0.0), (100.05, 1.0)), &book, &config()), 1.0);
+    }
+
+    #[test]
+    fn clamps_to_the_configured_factors() {
+        let config = LiquidityConfig::default();
+        let thin = top((99.95, 0.1), (100.05, 0.1));
+        let deep = top((99.95, 500.0), (100.05, 500.0));
+        assert_eq!(estimate(&thin, &OrderBook::new(), &config), config.min_factor);
+        assert_eq!(estimate(&deep, &OrderBook::new(), &config), config.max_factor);
+    }
+}
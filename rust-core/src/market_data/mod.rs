@@ -0,0 +1,509 @@
+mod archive;
+mod arrival;
+mod bars;
+mod binance;
+pub mod capture;
+mod coinbase;
+mod fair_value;
+mod feed_manager;
+mod health;
+mod kraken;
+mod latency;
+mod liquidity;
+mod order_book;
+mod recorder;
+mod replay;
+mod sanity;
+mod snapshot;
+mod source;
+mod synthetic;
+mod time_series;
+mod top_of_book;
+mod trade_flow;
+mod volatility;
+mod ws;
+
+pub use archive::{parse_date_ns, ArchiveFile, ArchiveFilter, ArchiveImporter, ArchiveKind, NANOS_PER_DAY};
+pub use arrival::{ArrivalConfig, ArrivalEstimate, ArrivalEstimator};
+pub use bars::{Bar, BarAggregator, BarBuilder, BarSpec, DEFAULT_BAR_HISTORY};
+pub use binance::BinanceSource;
+pub use coinbase::CoinbaseSource;
+pub use fair_value::{FairValue, DEFAULT_IMBALANCE_LEVELS};
+pub use feed_manager::{shared_binance_manager, FeedManager};
+pub use health::{FeedHealth, FeedHealthSnapshot, FeedStatus, DEFAULT_STALE_AFTER};
+pub use kraken::KrakenSource;
+pub use latency::{FeedLatency, LatencyStats, StreamKind, LATENCY_SAMPLES};
+pub use liquidity::LiquidityConfig;
+pub use order_book::{BookDelta, DeltaOutcome, DepthSnapshot, OrderBook};
+pub use recorder::TickRecorder;
+pub use replay::{ReplayControl, ReplaySource, ReplaySpeed};
+pub use sanity::{SanityConfig, SanityCounters, SanityFilter};
+pub use snapshot::{FileSnapshotProvider, RestSnapshotProvider, SnapshotProvider};
+pub use source::{monotonic_time_ns, unix_time_ns, FeedError, MarketDataSource, MarketEvent, Side, TimedEvent};
+pub use synthetic::{PriceModel, SyntheticConfig, SyntheticGenerator, SyntheticSource, SECONDS_PER_YEAR};
+pub use time_series::{Samples, TimeSeries, PRICE_HISTORY_CAPACITY};
+pub use top_of_book::{TopOfBook, TopOfBookCell};
+pub use trade_flow::{TradeFlow, TradeFlowMetrics, DEFAULT_TRADE_WINDOW};
+pub use volatility::{VolatilityConfig, VolatilityEstimator};
+
+use feed_manager::{run_connection, shared_runtime, Subscription};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::Duration;
+
+struct FeedState {
+    top: TopOfBookCell,
+    /// `(receive_time_ns, last_price)` of recent ticker updates.
+    price_history: RwLock<TimeSeries<f64>>,
+    book: RwLock<OrderBook>,
+    recorder: RwLock<Option<TickRecorder>>,
+    trade_flow: RwLock<TradeFlow>,
+    arrival: RwLock<ArrivalEstimator>,
+    bars: RwLock<BarAggregator>,
+    latency: RwLock<FeedLatency>,
+    sanity: RwLock<SanityFilter>,
+    health: FeedHealth,
+    /// Set once the source delivers order book snapshots or deltas, after
+    /// which the top of book is only valid while the book is synced.
+    book_driven: AtomicBool,
+}
+
+impl FeedState {
+    /// Record an event received from a source, then apply whatever passes
+    /// the sanity filters. Returns whether the order book needs a fresh
+    /// snapshot before further deltas can be applied.
+    fn ingest(&self, timed: TimedEvent) -> bool {
+        if let Some(recorder) = self.recorder.read().unwrap().as_ref() {
+            recorder.record(&timed);
+        }
+        // Age the trade window on the feed's clock so replays stay consistent
+        self.trade_flow.write().unwrap().evict(timed.receive_time_ns);
+        self.arrival.write().unwrap().evict(timed.receive_time_ns);
+        self.bars.write().unwrap().advance(timed.receive_time_ns);
+        let times = (timed.exchange_time_ns, timed.receive_time_ns);
+        self.latency.write().unwrap().record(StreamKind::of(&timed.event), times.0, times.1);
+
+        match timed.event {
+            MarketEvent::Ticker { last_price, .. } => {
+                if !self.sanity.write().unwrap().accept_print(last_price, None) {
+                    return false;
+                }
+                self.top.update(|top| {
+                    top.last_price = last_price;
+                    (top.exchange_time_ns, top.receive_time_ns) = times;
+                });
+                self.bars.write().unwrap().record_price(timed.receive_time_ns, last_price);
+                self.price_history.write().unwrap().push(timed.receive_time_ns, last_price);
+                false
+            }
+            MarketEvent::Depth { mut bids, mut asks, .. } => {
+                let mut sanity = self.sanity.write().unwrap();
+                sanity.filter_levels(&mut bids);
+                sanity.filter_levels(&mut asks);
+                drop(sanity);
+                if let (Some(&best_bid), Some(&best_ask)) = (bids.first(), asks.first()) {
+                    self.set_quotes(times, best_bid, best_ask);
+                }
+                false
+            }
+            MarketEvent::BookSnapshot { mut snapshot, .. } => {
+                self.book_driven.store(true, Ordering::Relaxed);
+                let mut sanity = self.sanity.write().unwrap();
+                sanity.filter_levels(&mut snapshot.bids);
+                sanity.filter_levels(&mut snapshot.asks);
+                drop(sanity);
+                let mut book = self.book.write().unwrap();
+                let outcome = book.apply_snapshot(snapshot);
+                self.update_top_of_book(times, &book);
+                outcome == DeltaOutcome::Gap
+            }
+            MarketEvent::BookDelta { delta, .. } => {
+                self.book_driven.store(true, Ordering::Relaxed);
+                let mut book = self.book.write().unwrap();
+                let outcome = book.apply_delta(delta);
+                self.update_top_of_book(times, &book);
+                !book.is_synced() && outcome != DeltaOutcome::Stale
+            }
+            MarketEvent::Trade { price, quantity, aggressor, .. } => {
+                if !self.sanity.write().unwrap().accept_print(price, Some(quantity)) {
+                    return false;
+                }
+                self.trade_flow.write().unwrap().record(timed.receive_time_ns, quantity, aggressor);
+                let top = self.top.load();
+                if top.bid > 0.0 && top.ask > 0.0 {
+                    let mid = (top.bid + top.ask) / 2.0;
+                    self.arrival.write().unwrap().record(timed.receive_time_ns, price, mid);
+                }
+                self.bars.write().unwrap().record_trade(timed.receive_time_ns, price, quantity);
+                false
+            }
+        }
+    }
+
+    fn update_top_of_book(&self, times: (u64, u64), book: &OrderBook) {
+        match (book.best_bid(), book.best_ask()) {
+            (Some(best_bid), Some(best_ask)) if book.is_synced() => self.set_quotes(times, best_bid, best_ask),
+            _ => self.clear_quotes(),
+        }
+    }
+
+    /// Withdraw the best bid and ask, keeping the last price, so nothing is
+    /// quoted off a book that is no longer maintained.
+    fn clear_quotes(&self) {
+        self.top.update(|top| {
+            (top.bid, top.bid_size) = (0.0, 0.0);
+            (top.ask, top.ask_size) = (0.0, 0.0);
+        });
+    }
+
+    /// Drop the order book and buffered deltas, for example when a new
+    /// connection starts a delta stream that cannot be bridged to the old one.
+    fn reset_book(&self) {
+        self.book.write().unwrap().reset();
+        self.clear_quotes();
+    }
+
+    /// Forget everything built from earlier events, for a source that went
+    /// back in time. Settings, counters, recording and health are kept.
+    fn reset(&self) {
+        self.book.write().unwrap().reset();
+        self.top.update(|top| *top = TopOfBook::default());
+        self.price_history.write().unwrap().clear();
+        self.trade_flow.write().unwrap().clear();
+        self.arrival.write().unwrap().clear();
+        self.bars.write().unwrap().clear();
+        self.sanity.write().unwrap().clear();
+    }
+
+    /// False while a delta-driven book waits for a snapshot, or while the
+    /// sanity filters see it crossed (or locked, if configured).
+    fn book_valid(&self) -> bool {
+        let synced = !self.book_driven.load(Ordering::Relaxed) || self.book.read().unwrap().is_synced();
+        synced && self.sanity.read().unwrap().book_valid()
+    }
+
+    /// Publish best bid and ask `(price, size)` together so readers never
+    /// see one side from a different update than the other. `times` is the
+    /// event's `(exchange_time_ns, receive_time_ns)`.
+    fn set_quotes(&self, times: (u64, u64), bid: (f64, f64), ask: (f64, f64)) {
+        self.top.update(|top| {
+            (top.bid, top.bid_size) = bid;
+            (top.ask, top.ask_size) = ask;
+            (top.exchange_time_ns, top.receive_time_ns) = times;
+        });
+        self.sanity.write().unwrap().check_book(bid.0, ask.0);
+    }
+}
+
+#[derive(Clone)]
+pub struct MarketDataFeed {
+    _symbol: String,
+    state: Arc<FeedState>,
+    snapshot_provider: Option<Arc<dyn SnapshotProvider>>,
+    replay: Option<ReplayControl>,
+    base_url: Option<String>,
+    /// Route feeding `state`, removed when the last clone is dropped.
+    subscription: Option<Arc<Subscription>>,
+}
+
+impl MarketDataFeed {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            _symbol: symbol,
+            state: Arc::new(FeedState {
+                top: TopOfBookCell::default(),
+                price_history: RwLock::new(TimeSeries::with_capacity(PRICE_HISTORY_CAPACITY)),
+                book: RwLock::new(OrderBook::new()),
+                recorder: RwLock::new(None),
+                trade_flow: RwLock::new(TradeFlow::new(DEFAULT_TRADE_WINDOW)),
+                arrival: RwLock::new(ArrivalEstimator::new(ArrivalConfig::default())),
+                bars: RwLock::new(BarAggregator::default()),
+                latency: RwLock::new(FeedLatency::default()),
+                sanity: RwLock::new(SanityFilter::default()),
+                health: FeedHealth::default(),
+                book_driven: AtomicBool::new(false),
+            }),
+            snapshot_provider: None,
+            replay: None,
+            base_url: None,
+            subscription: None,
+        }
+    }
+
+    /// Subscribe this symbol on the process-wide Binance connection, or on a
+    /// dedicated connection to the endpoint set with `set_base_url`.
+    pub fn start(&mut self) {
+        match self.base_url.clone() {
+            Some(base_url) => {
+                if self.snapshot_provider.is_none() {
+                    let rest_url = rest_url_for(&base_url);
+                    self.set_snapshot_provider(Arc::new(RestSnapshotProvider::with_base_url(&rest_url)));
+                }
+                self.start_with_source(Box::new(BinanceSource::with_base_url(&base_url)));
+            }
+            None => {
+                if self.snapshot_provider.is_none() {
+                    self.set_snapshot_provider(Arc::new(RestSnapshotProvider::new()));
+                }
+                self.start_on(shared_binance_manager());
+            }
+        }
+    }
+
+    /// Subscribe this symbol on `manager`'s connection, next to any other
+    /// feeds for the same symbol. The route is removed once this feed and
+    /// all its clones are dropped, or when the feed is started again.
+    pub fn start_on(&mut self, manager: &FeedManager) {
+        let subscription = manager.attach(&self._symbol, self.state.clone(), self.snapshot_provider.clone());
+        self.subscription = Some(Arc::new(subscription));
+    }
+
+    /// Point `start` at a Binance-compatible stream endpoint (for example a
+    /// local stand-in exchange) instead of stream.binance.com. Unless a
+    /// snapshot provider is set, order book snapshots are then fetched from
+    /// `/api/v3/depth` on the same host.
+    pub fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
+    /// Set where order book snapshots are fetched from when the delta stream
+    /// needs (re)synchronizing. Without a provider the book only syncs from
+    /// `BookSnapshot` events emitted by the source itself.
+    pub fn set_snapshot_provider(&mut self, provider: Arc<dyn SnapshotProvider>) {
+        self.snapshot_provider = Some(provider);
+    }
+
+    /// Drive the feed from a dedicated source on the shared runtime, which is
+    /// closed once this feed and all its clones are dropped.
+    pub fn start_with_source(&mut self, source: Box<dyn MarketDataSource>) {
+        let (commands, receiver) = tokio::sync::mpsc::unbounded_channel();
+        shared_runtime().spawn(run_connection(source, receiver));
+        // The connection ends once this subscription, its only route, is dropped
+        let subscription = Subscription::new(&commands, &self._symbol, self.state.clone(), self.snapshot_provider.clone());
+        self.subscription = Some(Arc::new(subscription));
+    }
+
+    /// Drive the feed from capture files instead of a live exchange.
+    pub fn start_replay(&mut self, paths: Vec<PathBuf>, speed: ReplaySpeed) -> Result<ReplayControl, FeedError> {
+        let source = ReplaySource::new(paths, speed);
+        source.validate()?;
+        let control = source.control();
+        self.replay = Some(control.clone());
+        self.start_with_source(Box::new(source));
+        Ok(control)
+    }
+
+    /// Control handle of the replay started by `start_replay`, if any.
+    pub fn replay_control(&self) -> Option<&ReplayControl> {
+        self.replay.as_ref()
+    }
+
+    /// Apply a single event synchronously, bypassing any source (it is still
+    /// recorded). Returns `true` if the order book is waiting for a snapshot.
+    pub fn apply_event(&self, event: MarketEvent) -> bool {
+        self.state.ingest(TimedEvent::received_now(0, event))
+    }
+
+    /// Append every event received from here on to a capture file at `path`.
+    pub fn start_recording(&self, path: &str) -> Result<(), FeedError> {
+        let recorder = TickRecorder::create(path)?;
+        // Replace under the lock, but flush any previous recorder after it
+        let previous = self.state.recorder.write().unwrap().replace(recorder);
+        drop(previous);
+        Ok(())
+    }
+
+    /// Stop recording and flush the capture file.
+    pub fn stop_recording(&self) {
+        // Flushing joins the writer thread, so do it without holding the lock
+        let recorder = self.state.recorder.write().unwrap().take();
+        drop(recorder);
+    }
+
+    /// Set the length of the rolling window used for trade-flow metrics.
+    pub fn set_trade_window(&self, window: Duration) {
+        self.state.trade_flow.write().unwrap().set_window(window);
+    }
+
+    /// Set the window and distance grid used to fit order arrival. Trades
+    /// already recorded are kept and aged out under the new window.
+    pub fn set_arrival_config(&self, config: ArrivalConfig) {
+        self.state.arrival.write().unwrap().set_config(config);
+    }
+
+    /// Fill intensity `A·exp(−k·δ)` fitted to recent trade distances from
+    /// the mid, once enough trades have been seen.
+    pub fn arrival_estimate(&self) -> Option<ArrivalEstimate> {
+        self.state.arrival.read().unwrap().estimate()
+    }
+
+    /// Receive minus exchange time percentiles for one stream.
+    pub fn latency(&self, stream: StreamKind) -> LatencyStats {
+        self.state.latency.read().unwrap().stats(stream)
+    }
+
+    /// Estimated local minus exchange clock offset in nanoseconds, once any
+    /// exchange-timestamped event has arrived.
+    pub fn clock_offset_ns(&self) -> Option<i64> {
+        self.state.latency.read().unwrap().clock_offset_ns()
+    }
+
+    /// Replace the sanity filter settings; counters and recent prints are kept.
+    pub fn set_sanity_config(&self, config: SanityConfig) {
+        self.state.sanity.write().unwrap().set_config(config);
+    }
+
+    /// What the sanity filters have rejected or flagged so far.
+    pub fn sanity_counters(&self) -> SanityCounters {
+        self.state.sanity.read().unwrap().counters()
+    }
+
+    /// False while the book is crossed (or locked, if configured), or while
+    /// an order book fed by deltas is waiting for a snapshot; quoting must
+    /// stop until it recovers.
+    pub fn book_valid(&self) -> bool {
+        self.state.book_valid()
+    }
+
+    /// Buy/sell volume, imbalance and trade count over the trade window.
+    pub fn trade_flow(&self) -> TradeFlowMetrics {
+        self.state.trade_flow.read().unwrap().metrics()
+    }
+
+    /// Start building bars for `spec` in addition to the default 1s, 1m and
+    /// 5m time bars. Returns `false` if the spec is invalid.
+    pub fn add_bar_series(&self, spec: BarSpec) -> bool {
+        self.state.bars.write().unwrap().add_series(spec)
+    }
+
+    pub fn remove_bar_series(&self, spec: BarSpec) {
+        self.state.bars.write().unwrap().remove_series(spec);
+    }
+
+    /// Up to `count` most recent bars of a series, oldest first and ending
+    /// with the bar still being built. Empty if the series does not exist.
+    pub fn bars(&self, spec: BarSpec, count: usize) -> Vec<Bar> {
+        let bars = self.state.bars.read().unwrap();
+        bars.series(spec).map(|series| series.latest(count)).unwrap_or_default()
+    }
+
+    /// Connection state, message age, reconnects and errors of the stream
+    /// driving this feed.
+    pub fn health(&self) -> &FeedHealth {
+        &self.state.health
+    }
+
+    /// Read access to recent `(receive_time_ns, last_price)` ticker samples.
+    pub fn price_history(&self) -> RwLockReadGuard<'_, TimeSeries<f64>> {
+        self.state.price_history.read().unwrap()
+    }
+
+    /// Read access to the full price-level order book.
+    pub fn order_book(&self) -> RwLockReadGuard<'_, OrderBook> {
+        self.state.book.read().unwrap()
+    }
+
+    /// Consistent best bid/ask, sizes and last price from a single update.
+    /// Prefer this over the individual accessors when several are needed.
+    pub fn top_of_book(&self) -> TopOfBook {
+        self.state.top.load()
+    }
+
+    pub fn current_price(&self) -> f64 {
+        self.top_of_book().last_price
+    }
+
+    pub fn current_bid(&self) -> f64 {
+        self.top_of_book().bid
+    }
+
+    pub fn current_ask(&self) -> f64 {
+        self.top_of_book().ask
+    }
+
+    /// Reference price under `model` for a `top_of_book` snapshot, reading
+    /// the full book as well for multi-level imbalance.
+    pub fn fair_value(&self, model: FairValue, top: &TopOfBook) -> f64 {
+        fair_value::estimate(model, top, &self.state.book.read().unwrap())
+    }
+
+    /// Annualized realized volatility of recent ticker prices using the
+    /// estimator, sampling interval and horizon in `config`.
+    pub fn realized_volatility(&self, config: &VolatilityConfig) -> f64 {
+        volatility::estimate(&self.state.price_history.read().unwrap(), config)
+    }
+
+    /// Depth within `config.band_bps` of the mid relative to the reference
+    /// depth, clamped to the configured range. Higher factor = more liquid.
+    pub fn liquidity_factor(&self, config: &LiquidityConfig) -> f64 {
+        let top = self.top_of_book();
+        liquidity::estimate(&top, &self.state.book.read().unwrap(), config)
+    }
+}
+
+/// REST base URL on the same host as a WebSocket base URL.
+fn rest_url_for(base_url: &str) -> String {
+    if let Some(rest) = base_url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = base_url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else {
+        base_url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_event(last_update_id: u64) -> MarketEvent {
+        let snapshot = DepthSnapshot { last_update_id, bids: vec![(100.0, 1.0)], asks: vec![(101.0, 1.0)] };
+        MarketEvent::BookSnapshot { symbol: "BTCUSDT".into(), snapshot }
+    }
+
+    fn delta_event(first_update_id: u64, final_update_id: u64) -> MarketEvent {
+        let delta = BookDelta { first_update_id, final_update_id, bids: vec![(100.5, 2.0)], asks: Vec::new() };
+        MarketEvent::BookDelta { symbol: "BTCUSDT".into(), delta }
+    }
+
+    #[test]
+    fn withdraws_top_of_book_while_the_book_is_unsynced() {
+        let feed = MarketDataFeed::new("BTCUSDT".into());
+        assert!(feed.book_valid());
+        assert!(feed.apply_event(delta_event(11, 11)));
+        assert!(!feed.book_valid());
+
+        feed.apply_event(snapshot_event(10));
+        assert!(feed.book_valid());
+        assert_eq!((feed.current_bid(), feed.current_ask()), (100.5, 101.0));
+
+        assert!(feed.apply_event(delta_event(20, 21)));
+        assert!(!feed.book_valid());
+        assert_eq!((feed.current_bid(), feed.current_ask()), (0.0, 0.0));
+
+        feed.apply_event(snapshot_event(30));
+        feed.state.reset_book();
+        assert!(!feed.book_valid());
+        assert_eq!(feed.top_of_book().bid_size, 0.0);
+    }
+
+    #[test]
+    fn reset_forgets_state_built_from_earlier_events() {
+        let feed = MarketDataFeed::new("BTCUSDT".into());
+        let symbol = || "BTCUSDT".to_string();
+        feed.apply_event(snapshot_event(10));
+        feed.apply_event(MarketEvent::Ticker { symbol: symbol(), last_price: 100.5 });
+        feed.apply_event(MarketEvent::Trade { symbol: symbol(), price: 100.5, quantity: 1.0, aggressor: Side::Buy });
+        let one_second = BarSpec::Time(Duration::from_secs(1));
+        assert_eq!(feed.bars(one_second, 10).len(), 1);
+
+        feed.state.reset();
+        assert_eq!(feed.top_of_book(), TopOfBook { sequence: feed.top_of_book().sequence, ..TopOfBook::default() });
+        assert!(feed.order_book().last_update_id().is_none());
+        assert!(feed.price_history().is_empty());
+        assert_eq!(feed.trade_flow().trade_count, 0);
+        assert!(feed.bars(one_second, 10).is_empty());
+        assert!(!feed.book_valid());
+    }
+}
@@ -0,0 +1,389 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Maximum number of delta events held while waiting for a snapshot.
+const MAX_BUFFERED_DELTAS: usize = 1000;
+
+/// Price key with a total order so levels can live in a `BTreeMap`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Price(pub(super) f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Full order book snapshot. `last_update_id` is the venue's book sequence
+/// number (Binance `lastUpdateId`), or a locally assigned one for venues
+/// whose book channel is not sequenced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// Incremental order book update covering sequence numbers
+/// `first_update_id..=final_update_id`. Quantities are absolute; a quantity
+/// of zero removes the level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookDelta {
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaOutcome {
+    /// The delta was applied to the book.
+    Applied,
+    /// The delta predates the book and was dropped.
+    Stale,
+    /// The book has no snapshot yet; the delta was buffered.
+    Buffered,
+    /// A sequence gap was detected; the book is invalid until a new snapshot.
+    Gap,
+}
+
+/// Assigns per-symbol sequence numbers to book messages from venues that do
+/// not sequence their book channel, relying on the connection's ordering.
+/// Each snapshot or delta takes the next number, so deltas chain onto the
+/// latest snapshot and anything received before it is dropped as stale.
+///
+/// When the venue's own sequence or checksum shows the book missed updates,
+/// `gap` skips a number so the book stops applying deltas until the fresh
+/// snapshot a resubscription brings.
+#[derive(Debug, Default)]
+pub(super) struct BookSequencer {
+    last: HashMap<String, u64>,
+    /// Last connection-wide message number seen, for venues that send one.
+    last_message: Option<u64>,
+    /// Symbols whose book channel must be resubscribed.
+    resync: Vec<String>,
+}
+
+impl BookSequencer {
+    pub(super) fn snapshot(&mut self, symbol: &str, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)>) -> DepthSnapshot {
+        DepthSnapshot { last_update_id: self.next(symbol), bids, asks }
+    }
+
+    pub(super) fn delta(&mut self, symbol: &str, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)>) -> BookDelta {
+        let id = self.next(symbol);
+        BookDelta { first_update_id: id, final_update_id: id, bids, asks }
+    }
+
+    /// Empty delta past a skipped number, which the book sees as a gap, for a
+    /// symbol whose book missed updates. The symbol is queued for
+    /// resubscription.
+    pub(super) fn gap(&mut self, symbol: &str) -> BookDelta {
+        self.next(symbol);
+        if !self.resync.iter().any(|pending| pending == symbol) {
+            self.resync.push(symbol.to_string());
+        }
+        self.delta(symbol, Vec::new(), Vec::new())
+    }
+
+    /// Gap deltas for every symbol with a book.
+    pub(super) fn gap_all(&mut self) -> Vec<(String, BookDelta)> {
+        let mut symbols: Vec<String> = self.last.keys().cloned().collect();
+        symbols.sort();
+        symbols.into_iter().map(|symbol| (symbol.clone(), self.gap(&symbol))).collect()
+    }
+
+    /// Record the connection-wide number of a message. Returns `true` if
+    /// numbers were skipped since the previous message, meaning messages
+    /// were lost; a number going backwards starts a new count.
+    pub(super) fn message_number(&mut self, number: u64) -> bool {
+        let skipped = self.last_message.is_some_and(|last| number > last + 1);
+        self.last_message = Some(number);
+        skipped
+    }
+
+    /// Symbols queued by `gap` since the last call.
+    pub(super) fn take_resync(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.resync)
+    }
+
+    /// Forget the message count and pending resubscriptions of a closed
+    /// connection; a new one subscribes afresh.
+    pub(super) fn reconnected(&mut self) {
+        self.last_message = None;
+        self.resync.clear();
+    }
+
+    fn next(&mut self, symbol: &str) -> u64 {
+        let last = self.last.entry(symbol.to_string()).or_insert(0);
+        *last += 1;
+        *last
+    }
+}
+
+/// Price-level order book synchronized from a snapshot plus delta stream,
+/// following Binance's "how to manage a local order book" procedure, which
+/// also covers venues that number their updates locally.
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    last_update_id: Option<u64>,
+    buffered: VecDeque<BookDelta>,
+    gap_count: u64,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+
+    pub fn gap_count(&self) -> u64 {
+        self.gap_count
+    }
+
+    /// Replace the book with a snapshot and replay any buffered deltas.
+    /// Returns `DeltaOutcome::Gap` if the snapshot cannot be bridged to the
+    /// buffered deltas, in which case a newer snapshot is required.
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> DeltaOutcome {
+        self.bids.clear();
+        self.asks.clear();
+        set_levels(&mut self.bids, &snapshot.bids);
+        set_levels(&mut self.asks, &snapshot.asks);
+        self.last_update_id = Some(snapshot.last_update_id);
+
+        // After a gap the book is unsynced again and the rest are re-buffered.
+        let mut outcome = DeltaOutcome::Applied;
+        for delta in std::mem::take(&mut self.buffered) {
+            if self.apply_delta(delta) == DeltaOutcome::Gap {
+                outcome = DeltaOutcome::Gap;
+            }
+        }
+        outcome
+    }
+
+    /// Apply a delta if it continues the current sequence. An event is
+    /// accepted when `U <= last_update_id + 1 <= u`; anything older is stale
+    /// and anything newer means updates were missed.
+    pub fn apply_delta(&mut self, delta: BookDelta) -> DeltaOutcome {
+        let last_update_id = match self.last_update_id {
+            Some(id) => id,
+            None => {
+                self.buffer(delta);
+                return DeltaOutcome::Buffered;
+            }
+        };
+
+        if delta.final_update_id <= last_update_id {
+            return DeltaOutcome::Stale;
+        }
+        if delta.first_update_id > last_update_id + 1 {
+            self.gap_count += 1;
+            self.invalidate();
+            self.buffer(delta);
+            return DeltaOutcome::Gap;
+        }
+
+        set_levels(&mut self.bids, &delta.bids);
+        set_levels(&mut self.asks, &delta.asks);
+        self.last_update_id = Some(delta.final_update_id);
+        DeltaOutcome::Applied
+    }
+
+    /// Drop all levels and wait for a new snapshot.
+    pub fn invalidate(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.last_update_id = None;
+    }
+
+    /// Invalidate the book and discard any buffered deltas.
+    pub fn reset(&mut self) {
+        self.invalidate();
+        self.buffered.clear();
+    }
+
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids.iter().next_back().map(|(price, qty)| (price.0, *qty))
+    }
+
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks.iter().next().map(|(price, qty)| (price.0, *qty))
+    }
+
+    /// Bid levels from best to worst.
+    pub fn bids(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.bids.iter().rev().map(|(price, qty)| (price.0, *qty))
+    }
+
+    /// Ask levels from best to worst.
+    pub fn asks(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.asks.iter().map(|(price, qty)| (price.0, *qty))
+    }
+
+    pub fn depth(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
+    fn buffer(&mut self, delta: BookDelta) {
+        if self.buffered.len() >= MAX_BUFFERED_DELTAS {
+            self.buffered.pop_front();
+        }
+        self.buffered.push_back(delta);
+    }
+}
+
+pub(super) fn set_levels(side: &mut BTreeMap<Price, f64>, levels: &[(f64, f64)]) {
+    for &(price, quantity) in levels {
+        if quantity == 0.0 {
+            side.remove(&Price(price));
+        } else {
+            side.insert(Price(price), quantity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot { last_update_id, bids: vec![(100.0, 1.0), (99.0, 2.0)], asks: vec![(101.0, 1.0), (102.0, 2.0)] }
+    }
+
+    fn delta(first_update_id: u64, final_update_id: u64, bids: &[(f64, f64)]) -> BookDelta {
+        BookDelta { first_update_id, final_update_id, bids: bids.to_vec(), asks: Vec::new() }
+    }
+
+    #[test]
+    fn drops_deltas_the_snapshot_already_covers() {
+        let mut book = OrderBook::new();
+        book.apply_snapshot(snapshot(10));
+        assert_eq!(book.apply_delta(delta(5, 10, &[(100.0, 0.0)])), DeltaOutcome::Stale);
+        assert_eq!(book.best_bid(), Some((100.0, 1.0)));
+        assert_eq!(book.last_update_id(), Some(10));
+    }
+
+    #[test]
+    fn buffers_deltas_until_the_first_snapshot() {
+        let mut book = OrderBook::new();
+        assert_eq!(book.apply_delta(delta(9, 11, &[(100.5, 3.0)])), DeltaOutcome::Buffered);
+        assert_eq!(book.apply_delta(delta(12, 12, &[(99.0, 0.0)])), DeltaOutcome::Buffered);
+        assert!(!book.is_synced());
+        assert_eq!(book.best_bid(), None);
+
+        assert_eq!(book.apply_snapshot(snapshot(10)), DeltaOutcome::Applied);
+        assert_eq!(book.last_update_id(), Some(12));
+        assert_eq!(book.bids().collect::<Vec<_>>(), vec![(100.5, 3.0), (100.0, 1.0)]);
+    }
+
+    #[test]
+    fn detects_gaps_and_waits_for_a_new_snapshot() {
+        let mut book = OrderBook::new();
+        book.apply_snapshot(snapshot(10));
+        assert_eq!(book.apply_delta(delta(11, 12, &[(100.5, 3.0)])), DeltaOutcome::Applied);
+        assert_eq!(book.apply_delta(delta(14, 15, &[(100.6, 1.0)])), DeltaOutcome::Gap);
+        assert_eq!(book.gap_count(), 1);
+        assert!(!book.is_synced());
+        assert_eq!(book.depth(), (0, 0));
+
+        // The delta after the gap is kept and replayed onto the next snapshot
+        assert_eq!(book.apply_snapshot(snapshot(14)), DeltaOutcome::Applied);
+        assert_eq!(book.best_bid(), Some((100.6, 1.0)));
+        assert_eq!(book.last_update_id(), Some(15));
+    }
+
+    #[test]
+    fn bridges_the_snapshot_to_the_delta_spanning_it() {
+        // U <= lastUpdateId + 1 <= u: the first delta may start before the snapshot
+        let mut book = OrderBook::new();
+        book.apply_delta(delta(5, 8, &[(100.0, 5.0)]));
+        book.apply_delta(delta(9, 13, &[(100.5, 3.0)]));
+        book.apply_delta(delta(14, 14, &[(99.0, 0.0)]));
+        assert_eq!(book.apply_snapshot(snapshot(10)), DeltaOutcome::Applied);
+        assert_eq!(book.bids().collect::<Vec<_>>(), vec![(100.5, 3.0), (100.0, 1.0)]);
+        assert_eq!(book.last_update_id(), Some(14));
+
+        // A snapshot older than every buffered delta cannot be bridged
+        let mut book = OrderBook::new();
+        book.apply_delta(delta(12, 13, &[(100.5, 3.0)]));
+        assert_eq!(book.apply_snapshot(snapshot(10)), DeltaOutcome::Gap);
+        assert!(!book.is_synced());
+        assert_eq!(book.apply_snapshot(snapshot(12)), DeltaOutcome::Applied);
+        assert_eq!(book.last_update_id(), Some(13));
+    }
+
+    #[test]
+    fn reset_discards_buffered_deltas() {
+        let mut book = OrderBook::new();
+        book.apply_delta(delta(11, 11, &[(100.5, 3.0)]));
+        book.reset();
+        book.apply_snapshot(snapshot(10));
+        assert_eq!(book.best_bid(), Some((100.0, 1.0)));
+        assert_eq!(book.last_update_id(), Some(10));
+    }
+
+    #[test]
+    fn drops_the_oldest_buffered_delta_when_full() {
+        let mut book = OrderBook::new();
+        for id in 2..=MAX_BUFFERED_DELTAS as u64 + 2 {
+            book.apply_delta(delta(id, id, &[(100.5, id as f64)]));
+        }
+        // Delta 2 was dropped, so the snapshot at 1 no longer bridges
+        assert_eq!(book.apply_snapshot(snapshot(1)), DeltaOutcome::Gap);
+        assert_eq!(book.apply_snapshot(snapshot(2)), DeltaOutcome::Applied);
+        assert_eq!(book.best_bid(), Some((100.5, MAX_BUFFERED_DELTAS as f64 + 2.0)));
+    }
+
+    #[test]
+    fn sequencer_gap_holds_the_book_until_a_fresh_snapshot() {
+        let mut books = BookSequencer::default();
+        let mut book = OrderBook::new();
+        book.apply_snapshot(books.snapshot("BTC-USD", vec![(100.0, 1.0)], vec![(101.0, 1.0)]));
+        assert_eq!(book.apply_delta(books.delta("BTC-USD", vec![(100.5, 1.0)], Vec::new())), DeltaOutcome::Applied);
+
+        assert_eq!(book.apply_delta(books.gap("BTC-USD")), DeltaOutcome::Gap);
+        assert!(!book.is_synced());
+        assert_eq!(book.apply_delta(books.delta("BTC-USD", vec![(99.0, 1.0)], Vec::new())), DeltaOutcome::Buffered);
+        assert_eq!(books.take_resync(), vec!["BTC-USD".to_string()]);
+        assert!(books.take_resync().is_empty());
+
+        // Deltas sent before the resubscription snapshot are stale against it
+        book.apply_snapshot(books.snapshot("BTC-USD", vec![(98.0, 1.0)], vec![(101.0, 1.0)]));
+        assert_eq!(book.bids().collect::<Vec<_>>(), vec![(98.0, 1.0)]);
+        assert_eq!(book.apply_delta(books.delta("BTC-USD", vec![(98.5, 1.0)], Vec::new())), DeltaOutcome::Applied);
+    }
+
+    #[test]
+    fn sequencer_detects_skipped_message_numbers() {
+        let mut books = BookSequencer::default();
+        assert!(!books.message_number(0));
+        assert!(!books.message_number(1));
+        assert!(books.message_number(3));
+        // A new connection numbers from zero again
+        assert!(!books.message_number(0));
+        books.reconnected();
+        assert!(!books.message_number(5));
+    }
+}
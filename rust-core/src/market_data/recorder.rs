@@ -0,0 +1,87 @@
+use super::capture;
+use super::source::{FeedError, TimedEvent};
+use crossbeam::channel::{self, Sender};
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Appends every feed event to a binary capture file.
+///
+/// Events are handed to a dedicated writer thread over a channel so the feed
+/// never blocks on disk I/O. The file is flushed when the recorder is dropped.
+pub struct TickRecorder {
+    sender: Option<Sender<TimedEvent>>,
+    writer: Option<JoinHandle<()>>,
+    recorded: Arc<AtomicU64>,
+}
+
+impl TickRecorder {
+    /// Open `path` for appending, writing the capture header if the file is
+    /// new and validating it otherwise.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, FeedError> {
+        let path = path.as_ref();
+        let capture_err = |e: std::io::Error| FeedError::Capture(format!("{}: {}", path.display(), e));
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(capture_err)?;
+
+        if file.metadata().map_err(capture_err)?.len() == 0 {
+            capture::write_header(&mut file).map_err(capture_err)?;
+        } else {
+            let mut existing = std::fs::File::open(path).map_err(capture_err)?;
+            let version = capture::read_header(&mut existing)?;
+            if version != capture::CAPTURE_VERSION {
+                return Err(FeedError::Capture(format!(
+                    "{}: cannot append to a version {} capture",
+                    path.display(),
+                    version
+                )));
+            }
+        }
+
+        let (sender, receiver) = channel::unbounded::<TimedEvent>();
+        let recorded = Arc::new(AtomicU64::new(0));
+        let writer_recorded = recorded.clone();
+        let writer = std::thread::spawn(move || {
+            let mut out = BufWriter::new(file);
+            for timed in receiver {
+                if let Err(e) = capture::write_event(&mut out, &timed) {
+                    log::error!("tick recorder write failed: {}", e);
+                    return;
+                }
+                writer_recorded.fetch_add(1, Ordering::Relaxed);
+            }
+            if let Err(e) = out.flush() {
+                log::error!("tick recorder flush failed: {}", e);
+            }
+        });
+
+        Ok(Self { sender: Some(sender), writer: Some(writer), recorded })
+    }
+
+    pub fn record(&self, timed: &TimedEvent) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(timed.clone());
+        }
+    }
+
+    /// Number of events written to disk so far.
+    pub fn recorded(&self) -> u64 {
+        self.recorded.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for TickRecorder {
+    fn drop(&mut self) {
+        // Closing the channel lets the writer drain, flush and exit
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use super::capture;
+use super::source::{FeedError, MarketDataSource, MarketEvent, TimedEvent};
+use futures_util::future::BoxFuture;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Reproduce the recorded inter-arrival times.
+    RealTime,
+    /// Play back `n` times faster than recorded.
+    Multiplier(f64),
+    /// Deliver events without any delay.
+    AsFastAsPossible,
+}
+
+impl ReplaySpeed {
+    /// Map an FFI speed value: `<= 0` is as fast as possible, `1` is real time.
+    pub fn from_factor(factor: f64) -> Self {
+        if factor <= 0.0 || !factor.is_finite() {
+            Self::AsFastAsPossible
+        } else if factor == 1.0 {
+            Self::RealTime
+        } else {
+            Self::Multiplier(factor)
+        }
+    }
+
+    fn factor(self) -> Option<f64> {
+        match self {
+            Self::RealTime => Some(1.0),
+            Self::Multiplier(n) => Some(n),
+            Self::AsFastAsPossible => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ControlState {
+    speed: ReplaySpeed,
+    paused: bool,
+    seek_to_ns: Option<u64>,
+    position_ns: u64,
+    /// Bumped on every change so the source re-anchors its pacing clock.
+    generation: u64,
+}
+
+/// Handle for steering a running `ReplaySource` from another thread.
+#[derive(Debug, Clone)]
+pub struct ReplayControl {
+    state: Arc<Mutex<ControlState>>,
+}
+
+impl ReplayControl {
+    fn new(speed: ReplaySpeed) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ControlState {
+                speed,
+                paused: false,
+                seek_to_ns: None,
+                position_ns: 0,
+                generation: 0,
+            })),
+        }
+    }
+
+    pub fn set_speed(&self, speed: ReplaySpeed) {
+        let mut state = self.state.lock().unwrap();
+        state.speed = speed;
+        state.generation += 1;
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = paused;
+        state.generation += 1;
+    }
+
+    /// Jump to the first event received at or after `time_ns`. Events before
+    /// the target are delivered without delay so that the feed state (order
+    /// book, price history) is consistent at the target; seeking backwards
+    /// restarts from the beginning of the capture, and the feed driver
+    /// resets the feed state first.
+    pub fn seek(&self, time_ns: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.seek_to_ns = Some(time_ns);
+        state.generation += 1;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Receive time of the most recently delivered event.
+    pub fn position_ns(&self) -> u64 {
+        self.state.lock().unwrap().position_ns
+    }
+}
+
+/// Replays one or more capture files written by `TickRecorder`.
+pub struct ReplaySource {
+    paths: Vec<PathBuf>,
+    file_index: usize,
+    reader: Option<BufReader<File>>,
+    /// Format version of the open capture file.
+    version: u16,
+    /// Subscribed symbols; events for other symbols are skipped.
+    symbols: Vec<String>,
+    control: ReplayControl,
+    generation: u64,
+    /// Wall-clock instant and recorded time that pacing is measured from.
+    anchor: Option<(Instant, u64)>,
+    fast_forward_to_ns: Option<u64>,
+    /// Event read from the capture but not yet delivered.
+    held: Option<TimedEvent>,
+    /// Playback restarted from an earlier point since `take_rewind`.
+    rewound: bool,
+}
+
+impl ReplaySource {
+    pub fn new(paths: Vec<PathBuf>, speed: ReplaySpeed) -> Self {
+        Self {
+            paths,
+            file_index: 0,
+            reader: None,
+            version: capture::CAPTURE_VERSION,
+            symbols: Vec::new(),
+            control: ReplayControl::new(speed),
+            generation: 0,
+            anchor: None,
+            fast_forward_to_ns: None,
+            held: None,
+            rewound: false,
+        }
+    }
+
+    /// Check that every capture file exists and has a valid header.
+    pub fn validate(&self) -> Result<(), FeedError> {
+        for path in &self.paths {
+            let mut file = File::open(path).map_err(|e| FeedError::Capture(format!("{}: {}", path.display(), e)))?;
+            capture::read_header(&mut file)?;
+        }
+        Ok(())
+    }
+
+    pub fn control(&self) -> ReplayControl {
+        self.control.clone()
+    }
+
+    fn open(&mut self, index: usize) -> Result<(), FeedError> {
+        self.file_index = index;
+        self.reader = None;
+        if let Some(path) = self.paths.get(index) {
+            let file = File::open(path).map_err(|e| FeedError::Capture(format!("{}: {}", path.display(), e)))?;
+            let mut reader = BufReader::new(file);
+            self.version = capture::read_header(&mut reader)?;
+            self.reader = Some(reader);
+        }
+        Ok(())
+    }
+
+    /// Next recorded event across all files, or `None` when exhausted.
+    fn read_next(&mut self) -> Result<Option<TimedEvent>, FeedError> {
+        loop {
+            let reader = match self.reader.as_mut() {
+                Some(reader) => reader,
+                None => return Ok(None),
+            };
+            match capture::read_event(reader, self.version)? {
+                Some(timed) => return Ok(Some(timed)),
+                None => self.open(self.file_index + 1)?,
+            }
+        }
+    }
+
+    fn matches_symbol(&self, event: &MarketEvent) -> bool {
+        self.symbols.iter().any(|s| s.eq_ignore_ascii_case(event.symbol()))
+    }
+
+    /// Apply pending control changes. Returns the current speed, whether
+    /// playback is paused and whether a backward seek reopened the capture.
+    fn poll_control(&mut self) -> Result<(ReplaySpeed, bool, bool), FeedError> {
+        let (speed, paused, seek_to_ns, position_ns, generation) = {
+            let mut state = self.control.state.lock().unwrap();
+            (state.speed, state.paused, state.seek_to_ns.take(), state.position_ns, state.generation)
+        };
+        if generation != self.generation {
+            self.generation = generation;
+            self.anchor = None;
+        }
+        let mut reopened = false;
+        if let Some(target) = seek_to_ns {
+            if target < position_ns {
+                self.open(0)?;
+                reopened = true;
+                self.rewound = true;
+            }
+            self.fast_forward_to_ns = Some(target);
+        }
+        Ok((speed, paused, reopened))
+    }
+
+    /// Wait until the event recorded at `record_time_ns` is due, reacting to
+    /// control changes while waiting. Returns `false` if a backward seek made
+    /// the pending event obsolete.
+    async fn pace(&mut self, record_time_ns: u64) -> Result<bool, FeedError> {
+        loop {
+            let (speed, paused, reopened) = self.poll_control()?;
+            if reopened {
+                return Ok(false);
+            }
+            if self.fast_forward_to_ns.is_some() {
+                return Ok(true);
+            }
+            let factor = match speed.factor() {
+                Some(factor) => factor,
+                None => return Ok(true),
+            };
+            if paused {
+                tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+                continue;
+            }
+
+            let (anchor_instant, anchor_ns) = *self.anchor.get_or_insert((Instant::now(), record_time_ns));
+            let offset_ns = record_time_ns.saturating_sub(anchor_ns) as f64 / factor;
+            let due = anchor_instant + Duration::from_nanos(offset_ns as u64);
+            let now = Instant::now();
+            if due <= now {
+                return Ok(true);
+            }
+            tokio::time::sleep_until(due.min(now + PAUSE_POLL_INTERVAL)).await;
+        }
+    }
+}
+
+impl MarketDataSource for ReplaySource {
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>> {
+        Box::pin(async move {
+            self.anchor = None;
+            self.held = None;
+            // Reconnecting replays from the start of the capture
+            self.rewound = self.control.position_ns() > 0;
+            self.open(0)
+        })
+    }
+
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            if !self.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)) {
+                self.symbols.push(symbol.to_string());
+            }
+            Ok(())
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>> {
+        Box::pin(async move {
+            loop {
+                let (_, paused, reopened) = self.poll_control()?;
+                if reopened {
+                    self.held = None;
+                }
+                if paused && self.fast_forward_to_ns.is_none() {
+                    tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+                    continue;
+                }
+
+                let mut timed = match self.held.take() {
+                    Some(timed) => timed,
+                    None => match self.read_next()? {
+                        Some(timed) => timed,
+                        None => return Ok(None),
+                    },
+                };
+                if !self.matches_symbol(&timed.event) {
+                    continue;
+                }
+
+                let record_time_ns = timed.receive_time_ns;
+                match self.fast_forward_to_ns {
+                    Some(target) if record_time_ns < target => {}
+                    _ => {
+                        if self.fast_forward_to_ns.take().is_some() {
+                            self.anchor = None;
+                        }
+                        // Hold the event across the wait so a cancelled call
+                        // resumes with it instead of losing it
+                        self.held = Some(timed);
+                        if !self.pace(record_time_ns).await? {
+                            self.held = None;
+                            continue;
+                        }
+                        timed = match self.held.take() {
+                            Some(timed) => timed,
+                            None => continue,
+                        };
+                    }
+                }
+
+                self.control.state.lock().unwrap().position_ns = timed.receive_time_ns;
+                return Ok(Some(timed));
+            }
+        })
+    }
+
+    fn take_rewind(&mut self) -> bool {
+        std::mem::take(&mut self.rewound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tokio::time::timeout;
+
+    const MS: u64 = 1_000_000;
+
+    /// Capture of tickers received at `times_ms`, removed when dropped.
+    struct Capture(PathBuf);
+
+    impl Capture {
+        fn new(name: &str, times_ms: &[u64]) -> Self {
+            let path = std::env::temp_dir().join(format!("tradechest-replay-{}-{}.tctk", name, std::process::id()));
+            let mut file = File::create(&path).unwrap();
+            capture::write_header(&mut file).unwrap();
+            for (i, &time_ms) in times_ms.iter().enumerate() {
+                let event = MarketEvent::Ticker { symbol: "BTCUSDT".into(), last_price: 100.0 + i as f64 };
+                let timed = TimedEvent { exchange_time_ns: 0, receive_time_ns: time_ms * MS, event };
+                capture::write_event(&mut file, &timed).unwrap();
+            }
+            Self(path)
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    async fn start(path: &Path, speed: ReplaySpeed) -> ReplaySource {
+        let mut source = ReplaySource::new(vec![path.to_path_buf()], speed);
+        source.subscribe("BTCUSDT").await.unwrap();
+        source.connect().await.unwrap();
+        source
+    }
+
+    async fn next_ms(source: &mut ReplaySource) -> Option<u64> {
+        source.next_event().await.unwrap().map(|timed| timed.receive_time_ns / MS)
+    }
+
+    #[tokio::test]
+    async fn paces_recorded_gaps_by_the_speed_factor() {
+        let capture = Capture::new("pacing", &[0, 200, 400]);
+        let mut source = start(&capture.0, ReplaySpeed::Multiplier(4.0)).await;
+        let started = Instant::now();
+        assert_eq!(next_ms(&mut source).await, Some(0));
+        assert_eq!(next_ms(&mut source).await, Some(200));
+        assert_eq!(next_ms(&mut source).await, Some(400));
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(95) && elapsed < Duration::from_millis(350), "{:?}", elapsed);
+        assert_eq!(next_ms(&mut source).await, None);
+
+        let capture = Capture::new("unpaced", &[0, 60_000]);
+        let mut source = start(&capture.0, ReplaySpeed::AsFastAsPossible).await;
+        let started = Instant::now();
+        assert_eq!(next_ms(&mut source).await, Some(0));
+        assert_eq!(next_ms(&mut source).await, Some(60_000));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn pause_holds_playback_until_resumed() {
+        let capture = Capture::new("pause", &[0, 1, 2]);
+        let mut source = start(&capture.0, ReplaySpeed::AsFastAsPossible).await;
+        let control = source.control();
+        assert_eq!(next_ms(&mut source).await, Some(0));
+
+        control.set_paused(true);
+        assert!(control.is_paused());
+        assert!(timeout(Duration::from_millis(150), source.next_event()).await.is_err());
+        control.set_paused(false);
+        assert_eq!(next_ms(&mut source).await, Some(1));
+        assert_eq!(next_ms(&mut source).await, Some(2));
+        assert_eq!(control.position_ns(), 2 * MS);
+    }
+
+    #[tokio::test]
+    async fn seeks_forward_through_and_back_to_the_target() {
+        let capture = Capture::new("seek", &[0, 1_000, 2_000, 3_000, 4_000]);
+        let mut source = start(&capture.0, ReplaySpeed::RealTime).await;
+        let control = source.control();
+        let started = Instant::now();
+        assert_eq!(next_ms(&mut source).await, Some(0));
+
+        // Skipped events are still delivered, without waiting, to keep the book consistent
+        control.seek(3_000 * MS);
+        for expected in [1_000, 2_000, 3_000] {
+            assert_eq!(next_ms(&mut source).await, Some(expected));
+        }
+        assert!(!source.take_rewind());
+
+        control.seek(1_000 * MS);
+        assert_eq!(next_ms(&mut source).await, Some(0));
+        assert!(source.take_rewind());
+        assert!(!source.take_rewind());
+        assert_eq!(next_ms(&mut source).await, Some(1_000));
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        // Past the target, playback is paced in real time again
+        assert!(timeout(Duration::from_millis(200), source.next_event()).await.is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanityConfig {
+    /// A print further than this from the rolling median is an outlier.
+    pub max_deviation_bps: f64,
+    /// Number of recent prints the median is taken over.
+    pub median_window: usize,
+    /// Outliers are only checked once the window holds this many prints.
+    pub min_samples: usize,
+    /// Drop outliers instead of only counting them.
+    pub reject_outliers: bool,
+    /// Treat a locked book (bid equal to ask) as invalid, like a crossed one.
+    pub halt_on_locked: bool,
+}
+
+impl Default for SanityConfig {
+    fn default() -> Self {
+        Self { max_deviation_bps: 500.0, median_window: 51, min_samples: 10, reject_outliers: true, halt_on_locked: true }
+    }
+}
+
+/// Running totals of what the filters caught since the feed was created.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SanityCounters {
+    /// Zero, negative or non-finite prices and trade sizes (always dropped).
+    pub invalid_prints: u64,
+    /// Prints too far from the rolling median (dropped if configured).
+    pub outliers: u64,
+    /// Zero-size levels dropped from depth updates and book snapshots.
+    pub zero_size_levels: u64,
+    /// Updates that left the book crossed (bid above ask).
+    pub crossed_books: u64,
+    /// Updates that left the book locked (bid equal to ask).
+    pub locked_books: u64,
+}
+
+/// Validates incoming prices and book state before they reach the feed
+/// state and, through it, the quoting engine.
+#[derive(Debug)]
+pub struct SanityFilter {
+    config: SanityConfig,
+    /// Recent valid prints, oldest first, including outliers so a genuine
+    /// jump moves the median once it persists.
+    recent: VecDeque<f64>,
+    counters: SanityCounters,
+    book_valid: bool,
+}
+
+impl Default for SanityFilter {
+    fn default() -> Self {
+        Self::new(SanityConfig::default())
+    }
+}
+
+impl SanityFilter {
+    pub fn new(config: SanityConfig) -> Self {
+        Self { config, recent: VecDeque::new(), counters: SanityCounters::default(), book_valid: true }
+    }
+
+    pub fn config(&self) -> &SanityConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: SanityConfig) {
+        while self.recent.len() > config.median_window {
+            self.recent.pop_front();
+        }
+        self.config = config;
+    }
+
+    pub fn counters(&self) -> SanityCounters {
+        self.counters
+    }
+
+    /// Whether the latest top of book was neither crossed nor (if
+    /// configured) locked.
+    pub fn book_valid(&self) -> bool {
+        self.book_valid
+    }
+
+    /// Forget recent prints and the book state; counters are kept.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.book_valid = true;
+    }
+
+    /// Check a traded or ticker price and its size (`None` for tickers).
+    /// Returns whether the print should be applied.
+    pub fn accept_print(&mut self, price: f64, quantity: Option<f64>) -> bool {
+        let valid = |value: f64| value.is_finite() && value > 0.0;
+        if !valid(price) || !quantity.is_none_or(valid) {
+            self.counters.invalid_prints += 1;
+            return false;
+        }
+
+        let outlier = self.recent.len() >= self.config.min_samples.max(1) && {
+            let median = median(&self.recent);
+            ((price - median) / median).abs() * 10_000.0 > self.config.max_deviation_bps
+        };
+        if self.config.median_window > 0 {
+            if self.recent.len() == self.config.median_window {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(price);
+        }
+        if outlier {
+            self.counters.outliers += 1;
+            return !self.config.reject_outliers;
+        }
+        true
+    }
+
+    /// Drop levels with zero, negative or non-finite size or price from a
+    /// full set of levels (not a delta, where zero size removes a level).
+    pub fn filter_levels(&mut self, levels: &mut Vec<(f64, f64)>) {
+        let before = levels.len();
+        levels.retain(|&(price, quantity)| price.is_finite() && price > 0.0 && quantity.is_finite() && quantity > 0.0);
+        self.counters.zero_size_levels += (before - levels.len()) as u64;
+    }
+
+    /// Re-evaluate the book after its best bid and ask changed. One-sided
+    /// or empty books are not invalid, only crossed or locked ones.
+    pub fn check_book(&mut self, bid: f64, ask: f64) {
+        let two_sided = bid > 0.0 && ask > 0.0;
+        let crossed = two_sided && bid > ask;
+        let locked = two_sided && bid == ask;
+        if crossed {
+            self.counters.crossed_books += 1;
+        }
+        if locked {
+            self.counters.locked_books += 1;
+        }
+        self.book_valid = !(crossed || (locked && self.config.halt_on_locked));
+    }
+}
+
+fn median(values: &VecDeque<f64>) -> f64 {
+    let mut prices: Vec<f64> = values.iter().copied().collect();
+    let mid = prices.len() / 2;
+    let (_, &mut median, _) = prices.select_nth_unstable_by(mid, f64::total_cmp);
+    median
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(reject_outliers: bool) -> SanityFilter {
+        SanityFilter::new(SanityConfig { max_deviation_bps: 100.0, median_window: 5, min_samples: 3, reject_outliers, ..SanityConfig::default() })
+    }
+
+    #[test]
+    fn drops_invalid_prints() {
+        let mut sanity = filter(true);
+        assert!(!sanity.accept_print(0.0, None));
+        assert!(!sanity.accept_print(-1.0, Some(1.0)));
+        assert!(!sanity.accept_print(f64::NAN, None));
+        assert!(!sanity.accept_print(100.0, Some(0.0)));
+        assert!(!sanity.accept_print(100.0, Some(f64::INFINITY)));
+        assert!(sanity.accept_print(100.0, Some(1.0)));
+        assert_eq!(sanity.counters(), SanityCounters { invalid_prints: 5, ..SanityCounters::default() });
+    }
+
+    #[test]
+    fn checks_outliers_only_once_the_median_window_warms_up() {
+        let mut sanity = filter(true);
+        // Too few prints for a median yet
+        assert!(sanity.accept_print(100.0, None));
+        assert!(sanity.accept_print(150.0, None));
+        assert!(sanity.accept_print(100.0, None));
+        assert_eq!(sanity.counters().outliers, 0);
+
+        // Within 1% of the median passes, beyond it does not
+        assert!(sanity.accept_print(100.9, None));
+        assert!(!sanity.accept_print(102.0, None));
+        assert_eq!(sanity.counters().outliers, 1);
+    }
+
+    #[test]
+    fn flag_only_mode_counts_outliers_but_applies_them() {
+        let mut sanity = filter(false);
+        for _ in 0..3 {
+            sanity.accept_print(100.0, None);
+        }
+        assert!(sanity.accept_print(120.0, None));
+        assert!(sanity.accept_print(80.0, None));
+        assert_eq!(sanity.counters().outliers, 2);
+    }
+
+    #[test]
+    fn a_persistent_jump_moves_the_median() {
+        let mut sanity = filter(true);
+        for _ in 0..5 {
+            sanity.accept_print(100.0, None);
+        }
+        // Rejected prints still enter the window, so the new level takes over
+        assert!(!sanity.accept_print(120.0, None));
+        assert!(!sanity.accept_print(120.0, None));
+        assert!(!sanity.accept_print(120.0, None));
+        assert!(sanity.accept_print(120.0, None));
+        assert!(!sanity.accept_print(100.0, None));
+
+        // Clearing restarts the warmup
+        sanity.clear();
+        assert!(sanity.accept_print(100.0, None));
+        assert_eq!(sanity.counters().outliers, 4);
+    }
+
+    #[test]
+    fn filters_zero_size_and_invalid_levels() {
+        let mut sanity = SanityFilter::default();
+        let mut levels = vec![(100.0, 1.0), (99.0, 0.0), (98.0, -1.0), (0.0, 1.0), (f64::NAN, 1.0), (97.0, 2.0)];
+        sanity.filter_levels(&mut levels);
+        assert_eq!(levels, vec![(100.0, 1.0), (97.0, 2.0)]);
+        assert_eq!(sanity.counters().zero_size_levels, 4);
+    }
+
+    #[test]
+    fn counts_crossed_and_locked_books() {
+        let mut sanity = SanityFilter::default();
+        sanity.check_book(101.0, 100.0);
+        assert!(!sanity.book_valid());
+        sanity.check_book(100.0, 100.0);
+        assert!(!sanity.book_valid());
+        sanity.check_book(100.0, 101.0);
+        assert!(sanity.book_valid());
+        // One-sided and empty books are not invalid
+        sanity.check_book(0.0, 101.0);
+        sanity.check_book(0.0, 0.0);
+        assert!(sanity.book_valid());
+        assert_eq!((sanity.counters().crossed_books, sanity.counters().locked_books), (1, 1));
+
+        let mut lenient = SanityFilter::new(SanityConfig { halt_on_locked: false, ..SanityConfig::default() });
+        lenient.check_book(100.0, 100.0);
+        assert!(lenient.book_valid());
+        lenient.check_book(100.5, 100.0);
+        assert!(!lenient.book_valid());
+        assert_eq!((lenient.counters().crossed_books, lenient.counters().locked_books), (1, 1));
+    }
+}
@@ -0,0 +1,92 @@
+use super::binance::parse_depth_snapshot;
+use super::order_book::DepthSnapshot;
+use super::source::FeedError;
+use futures_util::future::BoxFuture;
+use std::path::PathBuf;
+
+pub const BINANCE_REST_URL: &str = "https://api.binance.com";
+
+/// Supplies full depth snapshots used to (re)synchronize an `OrderBook`.
+pub trait SnapshotProvider: Send + Sync {
+    fn fetch<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<DepthSnapshot, FeedError>>;
+}
+
+/// Fetches snapshots from a Binance-compatible `/api/v3/depth` endpoint.
+pub struct RestSnapshotProvider {
+    base_url: String,
+    limit: u32,
+    client: reqwest::Client,
+}
+
+impl RestSnapshotProvider {
+    pub fn new() -> Self {
+        Self::with_base_url(BINANCE_REST_URL)
+    }
+
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            limit: 1000,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl Default for RestSnapshotProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapshotProvider for RestSnapshotProvider {
+    fn fetch<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<DepthSnapshot, FeedError>> {
+        Box::pin(async move {
+            let url = format!(
+                "{}/api/v3/depth?symbol={}&limit={}",
+                self.base_url,
+                symbol.to_uppercase(),
+                self.limit
+            );
+            let body = self
+                .client
+                .get(&url)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| FeedError::Snapshot(e.to_string()))?
+                .text()
+                .await
+                .map_err(|e| FeedError::Snapshot(e.to_string()))?;
+            parse_depth_snapshot(&body)
+                .ok_or_else(|| FeedError::Snapshot(format!("malformed snapshot from {}", url)))
+        })
+    }
+}
+
+/// Loads a snapshot saved from the REST endpoint to a local JSON file.
+pub struct FileSnapshotProvider {
+    path: PathBuf,
+}
+
+impl FileSnapshotProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SnapshotProvider for FileSnapshotProvider {
+    fn fetch<'a>(&'a self, _symbol: &'a str) -> BoxFuture<'a, Result<DepthSnapshot, FeedError>> {
+        Box::pin(async move {
+            let body = tokio::fs::read_to_string(&self.path)
+                .await
+                .map_err(|e| FeedError::Snapshot(format!("{}: {}", self.path.display(), e)))?;
+            parse_depth_snapshot(&body)
+                .ok_or_else(|| FeedError::Snapshot(format!("malformed snapshot in {}", self.path.display())))
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use super::order_book::{BookDelta, DepthSnapshot};
+use futures_util::future::BoxFuture;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Side of the aggressor (taker) in a trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// A single normalized update delivered by a market data source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketEvent {
+    /// 24h ticker update carrying the last traded price.
+    Ticker { symbol: String, last_price: f64 },
+    /// Top-of-book depth levels as (price, quantity), best level first.
+    Depth { symbol: String, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)> },
+    /// Full order book snapshot that (re)initializes the local book.
+    BookSnapshot { symbol: String, snapshot: DepthSnapshot },
+    /// Incremental order book update to apply on top of a snapshot.
+    BookDelta { symbol: String, delta: BookDelta },
+    /// Executed trade with the aggressor's side.
+    Trade { symbol: String, price: f64, quantity: f64, aggressor: Side },
+}
+
+impl MarketEvent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketEvent::Ticker { symbol, .. }
+            | MarketEvent::Depth { symbol, .. }
+            | MarketEvent::BookSnapshot { symbol, .. }
+            | MarketEvent::BookDelta { symbol, .. }
+            | MarketEvent::Trade { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// A market event stamped with the exchange event time (nanoseconds since
+/// the Unix epoch, 0 when the exchange does not provide one) and the local
+/// time it was received from `monotonic_time_ns`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+    pub exchange_time_ns: u64,
+    pub receive_time_ns: u64,
+    pub event: MarketEvent,
+}
+
+impl TimedEvent {
+    /// Stamp `event` as received now.
+    pub fn received_now(exchange_time_ns: u64, event: MarketEvent) -> Self {
+        Self { exchange_time_ns, receive_time_ns: monotonic_time_ns(), event }
+    }
+}
+
+pub fn unix_time_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Nanoseconds since the Unix epoch on a monotonic clock: the wall clock is
+/// read once, on first use, and advanced by `Instant` from then on. Receive
+/// times therefore never go backwards when the system clock is stepped, and
+/// stay comparable with exchange timestamps.
+pub fn monotonic_time_ns() -> u64 {
+    static ANCHOR: OnceLock<(Instant, u64)> = OnceLock::new();
+    let (instant, unix_ns) = *ANCHOR.get_or_init(|| (Instant::now(), unix_time_ns()));
+    unix_ns + instant.elapsed().as_nanos() as u64
+}
+
+/// Nanoseconds since the Unix epoch for an RFC 3339 timestamp such as
+/// `2023-02-09T20:32:50.714964855Z` or `2023-02-09T21:32:50.7+01:00`.
+pub(super) fn parse_rfc3339_ns(text: &str) -> Option<u64> {
+    let field = |range: std::ops::Range<usize>| text.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    if text.get(4..5)? != "-" || text.get(7..8)? != "-" || !matches!(text.get(10..11)?, "T" | "t" | " ") {
+        return None;
+    }
+
+    let mut rest = text.get(19..)?;
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+        let padded = format!("{:0<9}", &fraction[..digits.min(9)]);
+        nanos = padded.parse::<i64>().ok()?;
+        rest = &fraction[digits..];
+    }
+    let offset_minutes = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.get(0..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            sign * (rest.get(1..3)?.parse::<i64>().ok()? * 60 + rest.get(4..6)?.parse::<i64>().ok()?)
+        }
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset_minutes * 60;
+    u64::try_from(seconds.checked_mul(1_000_000_000)?.checked_add(nanos)?).ok()
+}
+
+/// Days since the Unix epoch for a proleptic Gregorian date (Howard
+/// Hinnant's algorithm).
+pub(super) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[derive(Debug, Error)]
+pub enum FeedError {
+    #[error("invalid feed url: {0}")]
+    InvalidUrl(String),
+    #[error("connection failed: {0}")]
+    Connect(String),
+    #[error("source is not connected")]
+    NotConnected,
+    #[error("connection closed by remote")]
+    Disconnected,
+    #[error("websocket error: {0}")]
+    WebSocket(String),
+    #[error("depth snapshot failed: {0}")]
+    Snapshot(String),
+    #[error("capture file error: {0}")]
+    Capture(String),
+    #[error("archive import failed: {0}")]
+    Archive(String),
+    #[error("no data received for {0:?}")]
+    Idle(Duration),
+}
+
+/// A provider of market events for one or more symbols.
+///
+/// The feed driver calls `subscribe` for every known symbol, then `connect`,
+/// then `next_event` until it returns `Ok(None)` (the source is exhausted) or
+/// an error (the driver reconnects after a delay, subscribing again first).
+/// Symbols added while connected are subscribed on the live connection, so
+/// `subscribe` must be idempotent, and `next_event` must be cancel-safe: the
+/// driver may drop a pending call and call it again without losing events.
+pub trait MarketDataSource: Send {
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>>;
+
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>>;
+
+    /// Drop `symbol` from the subscription set, so neither the live
+    /// connection nor later reconnects deliver it. Sources that only ever
+    /// serve the symbols they were built for keep the default of doing
+    /// nothing.
+    fn unsubscribe<'a>(&'a mut self, _symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>>;
+
+    /// Reconnect if a connected source delivers nothing for this long. Live
+    /// exchange connections can die silently; paced or paused sources such
+    /// as replays keep the default of no limit.
+    fn idle_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Whether the source jumped back in time since the last call, as a
+    /// replay does when seeking backwards. The driver then resets the state
+    /// built from earlier events before applying the next one.
+    fn take_rewind(&mut self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,397 @@
+use super::source::{FeedError, MarketDataSource, MarketEvent, Side, TimedEvent};
+use futures_util::future::BoxFuture;
+use std::collections::VecDeque;
+use tokio::time::{Duration, Instant};
+
+/// Crypto markets trade around the clock, so a year is 365 full days.
+pub const SECONDS_PER_YEAR: f64 = 365.0 * 86400.0;
+
+/// Stochastic process driving the synthetic mid price. Drift and volatility
+/// parameters are annualized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceModel {
+    /// Geometric Brownian motion: `dS/S = mu dt + sigma dW`.
+    Gbm { drift: f64, volatility: f64 },
+    /// Merton jump-diffusion: GBM plus Poisson jumps (`jump_intensity` per
+    /// year) with normally distributed log jump sizes.
+    JumpDiffusion {
+        drift: f64,
+        volatility: f64,
+        jump_intensity: f64,
+        jump_mean: f64,
+        jump_volatility: f64,
+    },
+    /// Heston stochastic volatility with variance mean-reverting to
+    /// `long_run_variance` at rate `mean_reversion`.
+    Heston {
+        drift: f64,
+        initial_variance: f64,
+        mean_reversion: f64,
+        long_run_variance: f64,
+        vol_of_vol: f64,
+        correlation: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticConfig {
+    pub model: PriceModel,
+    pub initial_price: f64,
+    pub seed: u64,
+    /// Simulated time between price steps.
+    pub tick_interval: Duration,
+    /// Quoted bid/ask spread around the mid, in basis points.
+    pub spread_bps: f64,
+    /// Quantity shown at the best bid and ask.
+    pub top_size: f64,
+    /// Mean number of trades per simulated second.
+    pub trade_rate: f64,
+    /// Mean trade quantity (exponentially distributed).
+    pub mean_trade_size: f64,
+    /// Pace events in real time; otherwise generate as fast as possible.
+    pub realtime: bool,
+    /// Simulated clock origin in nanoseconds since the Unix epoch.
+    pub start_time_ns: u64,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            model: PriceModel::Gbm { drift: 0.0, volatility: 0.6 },
+            initial_price: 50_000.0,
+            seed: 42,
+            tick_interval: Duration::from_millis(100),
+            spread_bps: 1.0,
+            top_size: 2.0,
+            trade_rate: 5.0,
+            mean_trade_size: 0.05,
+            realtime: true,
+            start_time_ns: 0,
+        }
+    }
+}
+
+/// Seeded path generator for the configured price model.
+pub struct SyntheticGenerator {
+    model: PriceModel,
+    rng: fastrand::Rng,
+    price: f64,
+    variance: f64,
+    dt_years: f64,
+}
+
+impl SyntheticGenerator {
+    pub fn new(model: PriceModel, initial_price: f64, tick_interval: Duration, seed: u64) -> Self {
+        let variance = match model {
+            PriceModel::Gbm { volatility, .. } | PriceModel::JumpDiffusion { volatility, .. } => volatility * volatility,
+            PriceModel::Heston { initial_variance, .. } => initial_variance,
+        };
+        Self {
+            model,
+            rng: fastrand::Rng::with_seed(seed),
+            price: initial_price,
+            variance,
+            dt_years: tick_interval.as_secs_f64() / SECONDS_PER_YEAR,
+        }
+    }
+
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    /// Current instantaneous variance (constant except under Heston).
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+
+    /// Advance one tick interval and return the new mid price.
+    pub fn step(&mut self) -> f64 {
+        let dt = self.dt_years;
+        let z = self.normal();
+        let log_return = match self.model {
+            PriceModel::Gbm { drift, volatility } => {
+                (drift - 0.5 * volatility * volatility) * dt + volatility * dt.sqrt() * z
+            }
+            PriceModel::JumpDiffusion { drift, volatility, jump_intensity, jump_mean, jump_volatility } => {
+                // Compensate the drift so jumps do not bias the expected return
+                let mean_jump = (jump_mean + 0.5 * jump_volatility * jump_volatility).exp() - 1.0;
+                let mut log_return = (drift - jump_intensity * mean_jump - 0.5 * volatility * volatility) * dt
+                    + volatility * dt.sqrt() * z;
+                for _ in 0..self.poisson(jump_intensity * dt) {
+                    log_return += jump_mean + jump_volatility * self.normal();
+                }
+                log_return
+            }
+            PriceModel::Heston { drift, mean_reversion, long_run_variance, vol_of_vol, correlation, .. } => {
+                // Full-truncation Euler keeps the variance usable when it dips below zero
+                let v = self.variance.max(0.0);
+                let z_v = correlation * z + (1.0 - correlation * correlation).sqrt() * self.normal();
+                self.variance += mean_reversion * (long_run_variance - v) * dt + vol_of_vol * (v * dt).sqrt() * z_v;
+                (drift - 0.5 * v) * dt + (v * dt).sqrt() * z
+            }
+        };
+        self.price *= log_return.exp();
+        self.price
+    }
+
+    /// Standard normal draw (Box-Muller).
+    fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.rng.f64(); // (0, 1] avoids ln(0)
+        let u2 = self.rng.f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Poisson draw by inversion (Knuth), suited to small means.
+    fn poisson(&mut self, mean: f64) -> u32 {
+        let limit = (-mean).exp();
+        let mut count = 0;
+        let mut product = self.rng.f64();
+        while product > limit {
+            count += 1;
+            product *= self.rng.f64();
+        }
+        count
+    }
+
+    fn exponential(&mut self, mean: f64) -> f64 {
+        -mean * (1.0 - self.rng.f64()).ln()
+    }
+}
+
+/// Market data source producing top-of-book, trades and ticker updates from
+/// a `SyntheticGenerator`. It simulates a single symbol: the one most
+/// recently subscribed.
+pub struct SyntheticSource {
+    config: SyntheticConfig,
+    generator: SyntheticGenerator,
+    symbol: String,
+    clock_ns: u64,
+    pending: VecDeque<TimedEvent>,
+    /// Wall-clock instant and simulated time that real-time pacing starts from.
+    anchor: Option<(Instant, u64)>,
+}
+
+impl SyntheticSource {
+    pub fn new(config: SyntheticConfig) -> Self {
+        let generator = SyntheticGenerator::new(config.model, config.initial_price, config.tick_interval, config.seed);
+        let clock_ns = config.start_time_ns;
+        Self {
+            config,
+            generator,
+            symbol: String::new(),
+            clock_ns,
+            pending: VecDeque::new(),
+            anchor: None,
+        }
+    }
+
+    /// Generate all events for one tick interval.
+    fn generate_step(&mut self) {
+        self.clock_ns += self.config.tick_interval.as_nanos() as u64;
+        let mid = self.generator.step();
+        let half_spread = mid * self.config.spread_bps / 20_000.0;
+        let (bid, ask) = (mid - half_spread, mid + half_spread);
+
+        self.push(MarketEvent::Depth {
+            symbol: self.symbol.clone(),
+            bids: vec![(bid, self.config.top_size)],
+            asks: vec![(ask, self.config.top_size)],
+        });
+
+        let mut last_price = mid;
+        let trades = self.generator.poisson(self.config.trade_rate * self.config.tick_interval.as_secs_f64());
+        for _ in 0..trades {
+            let aggressor = if self.generator.rng.bool() { Side::Buy } else { Side::Sell };
+            last_price = if aggressor == Side::Buy { ask } else { bid };
+            let quantity = self.generator.exponential(self.config.mean_trade_size);
+            self.push(MarketEvent::Trade { symbol: self.symbol.clone(), price: last_price, quantity, aggressor });
+        }
+
+        self.push(MarketEvent::Ticker { symbol: self.symbol.clone(), last_price });
+    }
+
+    fn push(&mut self, event: MarketEvent) {
+        self.pending.push_back(TimedEvent {
+            exchange_time_ns: self.clock_ns,
+            receive_time_ns: self.clock_ns,
+            event,
+        });
+    }
+}
+
+impl MarketDataSource for SyntheticSource {
+    fn connect(&mut self) -> BoxFuture<'_, Result<(), FeedError>> {
+        Box::pin(async move {
+            if self.clock_ns == 0 {
+                self.clock_ns = super::source::monotonic_time_ns();
+            }
+            self.anchor = Some((Instant::now(), self.clock_ns));
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(&'a mut self, symbol: &'a str) -> BoxFuture<'a, Result<(), FeedError>> {
+        Box::pin(async move {
+            self.symbol = symbol.to_uppercase();
+            Ok(())
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<Option<TimedEvent>, FeedError>> {
+        Box::pin(async move {
+            if self.pending.is_empty() {
+                if self.config.realtime {
+                    let (started, origin_ns) = *self.anchor.get_or_insert((Instant::now(), self.clock_ns));
+                    let elapsed_ns = self.clock_ns.saturating_sub(origin_ns);
+                    tokio::time::sleep_until(started + Duration::from_nanos(elapsed_ns)).await;
+                }
+                self.generate_step();
+            }
+            Ok(self.pending.pop_front())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    /// Log returns of `steps` ticks of `model`.
+    fn log_returns(model: PriceModel, tick_interval: Duration, steps: usize, seed: u64) -> Vec<f64> {
+        let mut generator = SyntheticGenerator::new(model, 100.0, tick_interval, seed);
+        let mut previous = generator.price();
+        (0..steps)
+            .map(|_| {
+                let price = generator.step();
+                let log_return = (price / previous).ln();
+                previous = price;
+                log_return
+            })
+            .collect()
+    }
+
+    fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+        (mean, variance)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64, what: &str) {
+        assert!((actual - expected).abs() <= tolerance, "{}: {} vs {}", what, actual, expected);
+    }
+
+    #[test]
+    fn paths_are_reproducible_from_the_seed() {
+        let model = PriceModel::Heston {
+            drift: 0.0,
+            initial_variance: 0.36,
+            mean_reversion: 2.0,
+            long_run_variance: 0.25,
+            vol_of_vol: 0.5,
+            correlation: -0.7,
+        };
+        let path = log_returns(model, DAY, 100, 7);
+        assert_eq!(log_returns(model, DAY, 100, 7), path);
+        assert_ne!(log_returns(model, DAY, 100, 8), path);
+    }
+
+    #[tokio::test]
+    async fn source_replays_the_same_events_for_a_seed() {
+        let events = |seed| async move {
+            let config = SyntheticConfig { seed, realtime: false, start_time_ns: 1_000, ..SyntheticConfig::default() };
+            let mut source = SyntheticSource::new(config);
+            source.subscribe("btcusdt").await.unwrap();
+            source.connect().await.unwrap();
+            let mut events = Vec::new();
+            for _ in 0..200 {
+                events.push(source.next_event().await.unwrap().unwrap());
+            }
+            events
+        };
+        let first = events(3).await;
+        assert_eq!(events(3).await, first);
+        assert_ne!(events(4).await, first);
+
+        assert!(first.iter().all(|timed| timed.event.symbol() == "BTCUSDT"));
+        assert_eq!(first[0].receive_time_ns, 1_000 + 100_000_000);
+        assert!(first.windows(2).all(|pair| pair[0].receive_time_ns <= pair[1].receive_time_ns));
+        assert!(first.iter().any(|timed| matches!(timed.event, MarketEvent::Trade { .. })));
+    }
+
+    #[test]
+    fn gbm_log_returns_have_the_configured_drift_and_volatility() {
+        let (drift, volatility) = (0.5, 0.6);
+        let steps = 100_000;
+        let returns = log_returns(PriceModel::Gbm { drift, volatility }, DAY, steps, 11);
+        let (mean, variance) = mean_and_variance(&returns);
+
+        let dt = DAY.as_secs_f64() / SECONDS_PER_YEAR;
+        let standard_error = volatility * (dt / steps as f64).sqrt();
+        assert_close(mean, (drift - 0.5 * volatility * volatility) * dt, 4.0 * standard_error, "mean");
+        assert_close((variance / dt).sqrt(), volatility, 0.02 * volatility, "volatility");
+    }
+
+    #[test]
+    fn jump_diffusion_adds_jump_variance_without_biasing_the_expected_return() {
+        let (volatility, jump_intensity, jump_mean, jump_volatility) = (0.3, 50.0, -0.02, 0.05);
+        let model = PriceModel::JumpDiffusion { drift: 0.0, volatility, jump_intensity, jump_mean, jump_volatility };
+        let steps = 200_000;
+        let returns = log_returns(model, DAY, steps, 13);
+        let (_, variance) = mean_and_variance(&returns);
+
+        let dt = DAY.as_secs_f64() / SECONDS_PER_YEAR;
+        let expected_variance = (volatility * volatility + jump_intensity * (jump_mean * jump_mean + jump_volatility * jump_volatility)) * dt;
+        assert_close(variance, expected_variance, 0.05 * expected_variance, "variance");
+
+        // The compensated drift keeps E[S(t + dt) / S(t)] at exp(drift * dt) = 1
+        let gross: Vec<f64> = returns.iter().map(|r| r.exp()).collect();
+        let (gross_mean, gross_variance) = mean_and_variance(&gross);
+        assert_close(gross_mean, 1.0, 4.0 * (gross_variance / steps as f64).sqrt(), "gross return");
+    }
+
+    #[test]
+    fn heston_variance_reverts_to_its_long_run_level() {
+        let (long_run_variance, correlation) = (0.04, -0.7);
+        let model = PriceModel::Heston {
+            drift: 0.0,
+            initial_variance: 0.25,
+            mean_reversion: 5.0,
+            long_run_variance,
+            vol_of_vol: 0.3,
+            correlation,
+        };
+        let hour = Duration::from_secs(3600);
+        let mut generator = SyntheticGenerator::new(model, 100.0, hour, 17);
+        let steps_per_year = (SECONDS_PER_YEAR / 3600.0) as usize;
+
+        // One year is five mean-reversion times, enough to forget the start
+        for _ in 0..steps_per_year {
+            generator.step();
+        }
+        let (mut variances, mut returns, mut variance_changes) = (Vec::new(), Vec::new(), Vec::new());
+        let mut previous = generator.price();
+        for _ in 0..10 * steps_per_year {
+            let variance = generator.variance();
+            let price = generator.step();
+            variances.push(variance);
+            returns.push((price / previous).ln());
+            variance_changes.push(generator.variance() - variance);
+            previous = price;
+        }
+
+        let (mean_variance, _) = mean_and_variance(&variances);
+        assert_close(mean_variance, long_run_variance, 0.1 * long_run_variance, "mean variance");
+        let (_, return_variance) = mean_and_variance(&returns);
+        assert_close(return_variance * steps_per_year as f64, mean_variance, 0.05 * mean_variance, "realized variance");
+
+        // Price and variance shocks share the configured correlation
+        let (return_mean, _) = mean_and_variance(&returns);
+        let (change_mean, change_variance) = mean_and_variance(&variance_changes);
+        let covariance = returns.iter().zip(&variance_changes).map(|(r, c)| (r - return_mean) * (c - change_mean)).sum::<f64>()
+            / (returns.len() - 1) as f64;
+        let sample_correlation = covariance / (return_variance * change_variance).sqrt();
+        assert_close(sample_correlation, correlation, 0.1, "correlation");
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+/// Ticker prices kept per symbol for volatility and signals.
+pub const PRICE_HISTORY_CAPACITY: usize = 4096;
+
+/// Fixed-capacity ring buffer of `(time_ns, value)` samples in time order.
+/// Storage is allocated once up front; once full, each push overwrites the
+/// oldest sample.
+#[derive(Debug, Clone)]
+pub struct TimeSeries<T> {
+    samples: Box<[(u64, T)]>,
+    /// Index of the oldest sample.
+    start: usize,
+    len: usize,
+}
+
+impl<T: Copy + Default> TimeSeries<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { samples: vec![(0, T::default()); capacity].into_boxed_slice(), start: 0, len: 0 }
+    }
+}
+
+impl<T: Copy> TimeSeries<T> {
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// Append a sample. A timestamp earlier than the latest sample means the
+    /// clock was rewound (for example a replay seeking backwards), so the
+    /// history restarts from this sample to keep it ordered.
+    pub fn push(&mut self, time_ns: u64, value: T) {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return;
+        }
+        if self.latest().is_some_and(|(latest_ns, _)| time_ns < latest_ns) {
+            self.clear();
+        }
+        if self.len == capacity {
+            self.samples[self.start] = (time_ns, value);
+            self.start = (self.start + 1) % capacity;
+        } else {
+            self.samples[(self.start + self.len) % capacity] = (time_ns, value);
+            self.len += 1;
+        }
+    }
+
+    pub fn latest(&self) -> Option<(u64, T)> {
+        self.len.checked_sub(1).map(|last| self.get(last))
+    }
+
+    /// All samples, oldest first.
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples { series: self, front: 0, back: self.len }
+    }
+
+    /// The `count` most recent samples, oldest first.
+    pub fn last_n(&self, count: usize) -> Samples<'_, T> {
+        Samples { series: self, front: self.len.saturating_sub(count), back: self.len }
+    }
+
+    /// Samples no older than `window` before the latest one, oldest first.
+    pub fn last_duration(&self, window: Duration) -> Samples<'_, T> {
+        let start_ns = match self.latest() {
+            Some((latest_ns, _)) => latest_ns.saturating_sub(window.as_nanos() as u64),
+            None => return self.iter(),
+        };
+        self.since(start_ns)
+    }
+
+    /// Samples at or after `start_ns`, oldest first.
+    pub fn since(&self, start_ns: u64) -> Samples<'_, T> {
+        // Binary search over logical positions; samples are time-ordered
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.get(mid).0 < start_ns {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Samples { series: self, front: low, back: self.len }
+    }
+
+    /// Sample at logical position `index` (0 = oldest).
+    fn get(&self, index: usize) -> (u64, T) {
+        self.samples[(self.start + index) % self.capacity()]
+    }
+}
+
+/// Iterator over a contiguous run of samples in a `TimeSeries`.
+#[derive(Debug, Clone)]
+pub struct Samples<'a, T> {
+    series: &'a TimeSeries<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<T: Copy> Iterator for Samples<'_, T> {
+    type Item = (u64, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.series.get(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T: Copy> DoubleEndedIterator for Samples<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.series.get(self.back))
+    }
+}
+
+impl<T: Copy> ExactSizeIterator for Samples<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Series of capacity 4 holding samples at times 1..=6, so the ring has
+    /// wrapped and the oldest sample sits at index 2 of the storage.
+    fn wrapped() -> TimeSeries<i32> {
+        let mut series = TimeSeries::with_capacity(4);
+        for time in 1..=6 {
+            series.push(time * 10, time as i32);
+        }
+        series
+    }
+
+    #[test]
+    fn overwrites_the_oldest_sample_once_full() {
+        let series = wrapped();
+        assert_eq!((series.len(), series.capacity()), (4, 4));
+        assert_eq!(series.iter().collect::<Vec<_>>(), vec![(30, 3), (40, 4), (50, 5), (60, 6)]);
+        assert_eq!(series.iter().rev().map(|(_, v)| v).collect::<Vec<_>>(), vec![6, 5, 4, 3]);
+        assert_eq!(series.latest(), Some((60, 6)));
+        assert_eq!(series.iter().len(), 4);
+    }
+
+    #[test]
+    fn last_n_takes_the_most_recent_samples() {
+        let series = wrapped();
+        assert_eq!(series.last_n(2).collect::<Vec<_>>(), vec![(50, 5), (60, 6)]);
+        assert_eq!(series.last_n(10).len(), 4);
+        assert_eq!(series.last_n(0).next(), None);
+    }
+
+    #[test]
+    fn time_windows_are_found_across_the_wrap_point() {
+        let series = wrapped();
+        // Storage holds [50, 60, 30, 40]: the search runs over logical positions
+        assert_eq!(series.since(35).map(|(t, _)| t).collect::<Vec<_>>(), vec![40, 50, 60]);
+        assert_eq!(series.since(50).map(|(t, _)| t).collect::<Vec<_>>(), vec![50, 60]);
+        assert_eq!(series.since(0).len(), 4);
+        assert_eq!(series.since(61).len(), 0);
+        assert_eq!(series.last_duration(Duration::from_nanos(20)).map(|(t, _)| t).collect::<Vec<_>>(), vec![40, 50, 60]);
+        assert_eq!(series.last_duration(Duration::from_secs(1)).len(), 4);
+
+        let empty: TimeSeries<i32> = TimeSeries::with_capacity(4);
+        assert_eq!(empty.last_duration(Duration::from_secs(1)).len(), 0);
+    }
+
+    #[test]
+    fn restarts_when_the_clock_is_rewound() {
+        let mut series = wrapped();
+        series.push(60, 7);
+        assert_eq!(series.len(), 4);
+        series.push(15, 8);
+        assert_eq!(series.iter().collect::<Vec<_>>(), vec![(15, 8)]);
+        series.push(25, 9);
+        assert_eq!(series.since(20).collect::<Vec<_>>(), vec![(25, 9)]);
+    }
+
+    #[test]
+    fn zero_capacity_holds_nothing() {
+        let mut series: TimeSeries<i32> = TimeSeries::with_capacity(0);
+        series.push(1, 1);
+        assert!(series.is_empty());
+        assert_eq!(series.latest(), None);
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Best bid/ask with sizes and the last traded price, as of one update.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TopOfBook {
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
+    pub last_price: f64,
+    /// Number of updates written so far; changes on every write.
+    pub sequence: u64,
+    pub exchange_time_ns: u64,
+    pub receive_time_ns: u64,
+}
+
+/// Fields stored per slot: the `TopOfBook` fields in declaration order.
+const FIELDS: usize = 8;
+
+/// Index of `TopOfBook::sequence` among the fields.
+const SEQUENCE: usize = 5;
+
+/// One copy of the fields, with a sequence that is odd while it is written.
+#[derive(Default)]
+struct Slot {
+    seq: AtomicU64,
+    fields: [AtomicU64; FIELDS],
+}
+
+impl Slot {
+    /// Fields if no write overlapped the read.
+    fn read(&self) -> Option<[u64; FIELDS]> {
+        let before = self.seq.load(Ordering::Acquire);
+        if before & 1 == 1 {
+            return None;
+        }
+        let raw = std::array::from_fn(|i| self.fields[i].load(Ordering::Relaxed));
+        fence(Ordering::Acquire);
+        (self.seq.load(Ordering::Relaxed) == before).then_some(raw)
+    }
+
+    fn write(&self, raw: [u64; FIELDS]) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (field, value) in self.fields.iter().zip(raw) {
+            field.store(value, Ordering::Relaxed);
+        }
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+}
+
+/// Double-buffered seqlock holding a `TopOfBook`. Each update is written to
+/// the slot readers are not directed to and then published, so a read never
+/// waits for a write in progress; it only retries if two updates completed
+/// while it was copying. Writers never wait on readers, every snapshot comes
+/// from exactly one update, and a thread never sees updates go backwards.
+#[derive(Default)]
+pub struct TopOfBookCell {
+    /// Updates published so far; slot `published % 2` holds the latest.
+    published: AtomicU64,
+    slots: [Slot; 2],
+    /// Serializes writers. Readers never take it.
+    writer: Mutex<()>,
+}
+
+impl TopOfBookCell {
+    /// Consistent copy of the latest update.
+    pub fn load(&self) -> TopOfBook {
+        loop {
+            let published = self.published.load(Ordering::Acquire);
+            // The slot may already hold a later update that is not published
+            // yet; returning it could put the next load behind this one
+            match self.slots[published as usize % 2].read() {
+                Some(raw) if raw[SEQUENCE] == published => return decode(raw),
+                _ => {}
+            }
+        }
+    }
+
+    /// Modify the current value and publish it as one update. `f` runs
+    /// before anything is written, so a panic in it leaves the cell as it
+    /// was. Concurrent writers are serialized against each other.
+    pub fn update(&self, f: impl FnOnce(&mut TopOfBook)) {
+        // A writer that panicked in `f` wrote nothing, so the lock is still sound
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let published = self.published.load(Ordering::Relaxed);
+        let mut top = self.load();
+        f(&mut top);
+        let raw = [
+            top.bid.to_bits(),
+            top.bid_size.to_bits(),
+            top.ask.to_bits(),
+            top.ask_size.to_bits(),
+            top.last_price.to_bits(),
+            published + 1,
+            top.exchange_time_ns,
+            top.receive_time_ns,
+        ];
+        self.slots[(published + 1) as usize % 2].write(raw);
+        self.published.store(published + 1, Ordering::Release);
+    }
+}
+
+fn decode(raw: [u64; FIELDS]) -> TopOfBook {
+    TopOfBook {
+        bid: f64::from_bits(raw[0]),
+        bid_size: f64::from_bits(raw[1]),
+        ask: f64::from_bits(raw[2]),
+        ask_size: f64::from_bits(raw[3]),
+        last_price: f64::from_bits(raw[4]),
+        sequence: raw[SEQUENCE],
+        exchange_time_ns: raw[6],
+        receive_time_ns: raw[7],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    #[test]
+    fn readers_never_see_a_torn_update() {
+        let cell = Arc::new(TopOfBookCell::default());
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let (cell, done) = (cell.clone(), done.clone());
+                std::thread::spawn(move || {
+                    let mut last_sequence = 0;
+                    let mut reads = 0u64;
+                    while !done.load(Ordering::Relaxed) || reads == 0 {
+                        let top = cell.load();
+                        if top.sequence == 0 {
+                            assert_eq!(top, TopOfBook::default());
+                            continue;
+                        }
+                        // Every field of update n is derived from n
+                        let n = top.bid;
+                        assert_eq!(
+                            [top.bid_size, top.ask - 1.0, top.ask_size, top.last_price - 0.5],
+                            [n, n, n, n],
+                            "torn read {:?}",
+                            top
+                        );
+                        assert_eq!((top.exchange_time_ns, top.receive_time_ns), (n as u64, n as u64));
+                        assert_eq!(top.sequence, n as u64);
+                        assert!(top.sequence >= last_sequence);
+                        last_sequence = top.sequence;
+                        reads += 1;
+                    }
+                })
+            })
+            .collect();
+
+        for n in 1..=200_000u64 {
+            cell.update(|top| {
+                let value = n as f64;
+                (top.bid, top.bid_size, top.ask, top.ask_size) = (value, value, value + 1.0, value);
+                top.last_price = value + 0.5;
+                (top.exchange_time_ns, top.receive_time_ns) = (n, n);
+            });
+        }
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(cell.load().sequence, 200_000);
+    }
+
+    #[test]
+    fn a_panicking_update_leaves_the_cell_readable_and_writable() {
+        let cell = TopOfBookCell::default();
+        cell.update(|top| top.last_price = 100.0);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cell.update(|top| {
+                top.last_price = 200.0;
+                panic!("writer failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!((cell.load().last_price, cell.load().sequence), (100.0, 1));
+
+        cell.update(|top| top.bid = 99.0);
+        assert_eq!((cell.load().bid, cell.load().last_price, cell.load().sequence), (99.0, 100.0, 2));
+    }
+}
@@ -0,0 +1,172 @@
+use super::source::Side;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub const DEFAULT_TRADE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Signed order-flow statistics over the trade window.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TradeFlowMetrics {
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    /// `(buy - sell) / (buy + sell)`, in `[-1, 1]`; 0 when there is no volume.
+    pub imbalance: f64,
+    pub trade_count: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WindowTrade {
+    time_ns: u64,
+    quantity: f64,
+    aggressor: Side,
+}
+
+/// Rolling time window of trades keeping running buy/sell volume totals.
+#[derive(Debug)]
+pub struct TradeFlow {
+    window_ns: u64,
+    trades: VecDeque<WindowTrade>,
+    buy_volume: f64,
+    sell_volume: f64,
+}
+
+impl TradeFlow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_ns: window.as_nanos() as u64,
+            trades: VecDeque::new(),
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_nanos(self.window_ns)
+    }
+
+    pub fn set_window(&mut self, window: Duration) {
+        self.window_ns = window.as_nanos() as u64;
+    }
+
+    /// Add a trade to the window. A timestamp earlier than the latest trade
+    /// means the clock was rewound, so the window restarts from this trade.
+    pub fn record(&mut self, time_ns: u64, quantity: f64, aggressor: Side) {
+        if self.trades.back().is_some_and(|latest| time_ns < latest.time_ns) {
+            self.clear();
+        }
+        self.trades.push_back(WindowTrade { time_ns, quantity, aggressor });
+        match aggressor {
+            Side::Buy => self.buy_volume += quantity,
+            Side::Sell => self.sell_volume += quantity,
+        }
+        self.evict(time_ns);
+    }
+
+    /// Drop every trade in the window.
+    pub fn clear(&mut self) {
+        self.trades.clear();
+        self.buy_volume = 0.0;
+        self.sell_volume = 0.0;
+    }
+
+    /// Drop trades older than the window ending at `now_ns`.
+    pub fn evict(&mut self, now_ns: u64) {
+        let cutoff = now_ns.saturating_sub(self.window_ns);
+        while let Some(trade) = self.trades.front() {
+            if trade.time_ns >= cutoff {
+                break;
+            }
+            match trade.aggressor {
+                Side::Buy => self.buy_volume -= trade.quantity,
+                Side::Sell => self.sell_volume -= trade.quantity,
+            }
+            self.trades.pop_front();
+        }
+        if self.trades.is_empty() {
+            // Clear accumulated floating-point drift
+            self.buy_volume = 0.0;
+            self.sell_volume = 0.0;
+        }
+    }
+
+    pub fn metrics(&self) -> TradeFlowMetrics {
+        let buy_volume = self.buy_volume.max(0.0);
+        let sell_volume = self.sell_volume.max(0.0);
+        let total = buy_volume + sell_volume;
+        TradeFlowMetrics {
+            buy_volume,
+            sell_volume,
+            imbalance: if total > 0.0 { (buy_volume - sell_volume) / total } else { 0.0 },
+            trade_count: self.trades.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn keeps_running_totals_and_imbalance_sign() {
+        let mut flow = TradeFlow::new(Duration::from_secs(10));
+        assert_eq!(flow.metrics(), TradeFlowMetrics::default());
+
+        flow.record(SECOND, 3.0, Side::Buy);
+        flow.record(2 * SECOND, 1.0, Side::Sell);
+        let metrics = flow.metrics();
+        assert_eq!((metrics.buy_volume, metrics.sell_volume, metrics.trade_count), (3.0, 1.0, 2));
+        assert!((metrics.imbalance - 0.5).abs() < 1e-12);
+
+        flow.record(3 * SECOND, 6.0, Side::Sell);
+        let metrics = flow.metrics();
+        assert_eq!((metrics.buy_volume, metrics.sell_volume), (3.0, 7.0));
+        assert!((metrics.imbalance + 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn evicts_trades_older_than_the_window() {
+        let mut flow = TradeFlow::new(Duration::from_secs(10));
+        flow.record(SECOND, 2.0, Side::Buy);
+        flow.record(5 * SECOND, 1.0, Side::Sell);
+
+        // The cutoff is inclusive: a trade exactly one window old is kept
+        flow.evict(11 * SECOND);
+        assert_eq!(flow.metrics().trade_count, 2);
+
+        flow.record(12 * SECOND, 4.0, Side::Buy);
+        let metrics = flow.metrics();
+        assert_eq!((metrics.buy_volume, metrics.sell_volume, metrics.trade_count), (4.0, 1.0, 2));
+
+        flow.evict(30 * SECOND);
+        assert_eq!(flow.metrics(), TradeFlowMetrics::default());
+    }
+
+    #[test]
+    fn shrinking_the_window_drops_older_trades_on_the_next_eviction() {
+        let mut flow = TradeFlow::new(Duration::from_secs(60));
+        for second in 1..=10 {
+            flow.record(second * SECOND, 1.0, Side::Buy);
+        }
+        flow.set_window(Duration::from_secs(3));
+        assert_eq!(flow.window(), Duration::from_secs(3));
+
+        flow.evict(10 * SECOND);
+        let metrics = flow.metrics();
+        assert_eq!((metrics.buy_volume, metrics.trade_count), (4.0, 4));
+    }
+
+    #[test]
+    fn rewound_timestamp_restarts_the_window() {
+        let mut flow = TradeFlow::new(Duration::from_secs(10));
+        flow.record(50 * SECOND, 5.0, Side::Buy);
+        flow.record(51 * SECOND, 2.0, Side::Sell);
+
+        flow.record(20 * SECOND, 1.0, Side::Sell);
+        let metrics = flow.metrics();
+        assert_eq!((metrics.buy_volume, metrics.sell_volume, metrics.trade_count), (0.0, 1.0, 1));
+        assert_eq!(metrics.imbalance, -1.0);
+    }
+}
@@ -0,0 +1,231 @@
+use super::bars::{Bar, BarBuilder, BarSpec};
+use super::synthetic::SECONDS_PER_YEAR;
+use super::time_series::{Samples, TimeSeries};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolatilityEstimator {
+    /// Standard deviation of log returns sampled on a fixed time grid.
+    TimeSampled,
+    /// Exponentially weighted moving average of squared sampled returns
+    /// (RiskMetrics style) with decay `lambda`.
+    Ewma { lambda: f64 },
+    /// High-low range estimator over bars of one sample interval.
+    Parkinson,
+    /// Open-high-low-close estimator over bars of one sample interval.
+    GarmanKlass,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolatilityConfig {
+    pub estimator: VolatilityEstimator,
+    /// Spacing of the return grid, and the bar length for range estimators.
+    pub sample_interval: Duration,
+    /// How far back from the latest tick to look.
+    pub horizon: Duration,
+    /// Returned when there is not enough history to estimate.
+    pub default_volatility: f64,
+    pub min_volatility: f64,
+    pub max_volatility: f64,
+}
+
+impl Default for VolatilityConfig {
+    fn default() -> Self {
+        Self {
+            estimator: VolatilityEstimator::TimeSampled,
+            sample_interval: Duration::from_secs(10),
+            horizon: Duration::from_secs(15 * 60),
+            default_volatility: 0.3,
+            min_volatility: 0.1,
+            max_volatility: 2.0,
+        }
+    }
+}
+
+/// Annualized volatility of the prices in `history` within the configured
+/// horizon, using 365-day annualization. Falls back to the configured
+/// default when the horizon holds fewer than two samples.
+pub fn estimate(history: &TimeSeries<f64>, config: &VolatilityConfig) -> f64 {
+    let interval_ns = config.sample_interval.as_nanos() as u64;
+    if interval_ns == 0 {
+        return config.default_volatility;
+    }
+    let window = history.last_duration(config.horizon);
+
+    let variance_per_interval = match config.estimator {
+        VolatilityEstimator::TimeSampled => time_sampled_variance(&sample_returns(window, interval_ns)),
+        VolatilityEstimator::Ewma { lambda } => ewma_variance(&sample_returns(window, interval_ns), lambda),
+        VolatilityEstimator::Parkinson => parkinson_variance(&bars(window, config.sample_interval)),
+        VolatilityEstimator::GarmanKlass => garman_klass_variance(&bars(window, config.sample_interval)),
+    };
+
+    match variance_per_interval {
+        Some(variance) => {
+            let intervals_per_year = SECONDS_PER_YEAR / config.sample_interval.as_secs_f64();
+            (variance * intervals_per_year)
+                .sqrt()
+                .clamp(config.min_volatility, config.max_volatility)
+        }
+        None => config.default_volatility,
+    }
+}
+
+/// Log returns between the last prices observed at each grid point
+/// (previous-tick sampling), carrying the price forward over quiet intervals.
+fn sample_returns(mut ticks: Samples<'_, f64>, interval_ns: u64) -> Vec<f64> {
+    let (first_ns, mut last_price) = match ticks.next() {
+        Some(tick) => tick,
+        None => return Vec::new(),
+    };
+    let mut returns = Vec::new();
+    let mut next_grid_ns = first_ns + interval_ns;
+    let mut sampled_price = last_price;
+    for (time_ns, price) in ticks {
+        while time_ns >= next_grid_ns {
+            returns.push((last_price / sampled_price).ln());
+            sampled_price = last_price;
+            next_grid_ns += interval_ns;
+        }
+        last_price = price;
+    }
+    returns
+}
+
+/// Time bars of one sample interval over the window, including the last
+/// partial bar.
+fn bars(ticks: Samples<'_, f64>, interval: Duration) -> Vec<Bar> {
+    let count = ticks.len();
+    let mut builder = BarBuilder::new(BarSpec::Time(interval), count);
+    for (time_ns, price) in ticks {
+        builder.record_price(time_ns, price);
+    }
+    builder.latest(count)
+}
+
+/// Realized variance per interval, assuming zero mean return.
+fn time_sampled_variance(returns: &[f64]) -> Option<f64> {
+    if returns.is_empty() {
+        return None;
+    }
+    Some(returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64)
+}
+
+fn ewma_variance(returns: &[f64], lambda: f64) -> Option<f64> {
+    let (first, rest) = returns.split_first()?;
+    Some(rest.iter().fold(first * first, |variance, r| lambda * variance + (1.0 - lambda) * r * r))
+}
+
+fn parkinson_variance(bars: &[Bar]) -> Option<f64> {
+    if bars.is_empty() {
+        return None;
+    }
+    let sum: f64 = bars.iter().map(|bar| (bar.high / bar.low).ln().powi(2)).sum();
+    Some(sum / (4.0 * std::f64::consts::LN_2 * bars.len() as f64))
+}
+
+fn garman_klass_variance(bars: &[Bar]) -> Option<f64> {
+    if bars.is_empty() {
+        return None;
+    }
+    let sum: f64 = bars
+        .iter()
+        .map(|bar| {
+            let range = (bar.high / bar.low).ln();
+            let body = (bar.close / bar.open).ln();
+            0.5 * range * range - (2.0 * std::f64::consts::LN_2 - 1.0) * body * body
+        })
+        .sum();
+    Some((sum / bars.len() as f64).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_data::{PriceModel, SyntheticGenerator};
+
+    const TRUE_VOLATILITY: f64 = 0.8;
+
+    fn series(ticks: &[(u64, f64)]) -> TimeSeries<f64> {
+        let mut series = TimeSeries::with_capacity(ticks.len());
+        for &(time_ns, price) in ticks {
+            series.push(time_ns, price);
+        }
+        series
+    }
+
+    /// One-second GBM ticks over `hours` hours with known volatility.
+    fn gbm_ticks(hours: u64, seed: u64) -> Vec<(u64, f64)> {
+        let model = PriceModel::Gbm { drift: 0.0, volatility: TRUE_VOLATILITY };
+        let mut generator = SyntheticGenerator::new(model, 30_000.0, Duration::from_secs(1), seed);
+        (0..hours * 3600)
+            .map(|second| (second * 1_000_000_000, generator.step()))
+            .collect()
+    }
+
+    fn config(estimator: VolatilityEstimator, sample_secs: u64) -> VolatilityConfig {
+        VolatilityConfig {
+            estimator,
+            sample_interval: Duration::from_secs(sample_secs),
+            horizon: Duration::from_secs(24 * 3600),
+            max_volatility: 10.0,
+            ..VolatilityConfig::default()
+        }
+    }
+
+    fn assert_close(estimate: f64, tolerance: f64) {
+        assert!(
+            (estimate - TRUE_VOLATILITY).abs() < tolerance,
+            "estimate {} too far from {}",
+            estimate,
+            TRUE_VOLATILITY
+        );
+    }
+
+    #[test]
+    fn time_sampled_recovers_gbm_volatility() {
+        let ticks = gbm_ticks(12, 1);
+        assert_close(estimate(&series(&ticks), &config(VolatilityEstimator::TimeSampled, 10)), 0.05);
+    }
+
+    #[test]
+    fn ewma_recovers_gbm_volatility() {
+        let ticks = gbm_ticks(12, 2);
+        let estimator = VolatilityEstimator::Ewma { lambda: 0.995 };
+        assert_close(estimate(&series(&ticks), &config(estimator, 10)), 0.1);
+    }
+
+    #[test]
+    fn range_estimators_recover_gbm_volatility() {
+        // Discrete sampling understates the true high-low range, so allow
+        // for a small downward bias with 60 ticks per bar
+        let ticks = gbm_ticks(12, 3);
+        assert_close(estimate(&series(&ticks), &config(VolatilityEstimator::Parkinson, 60)), 0.1);
+        assert_close(estimate(&series(&ticks), &config(VolatilityEstimator::GarmanKlass, 60)), 0.1);
+    }
+
+    #[test]
+    fn annualization_is_independent_of_sampling_interval() {
+        let ticks = gbm_ticks(12, 4);
+        let fine = estimate(&series(&ticks), &config(VolatilityEstimator::TimeSampled, 5));
+        let coarse = estimate(&series(&ticks), &config(VolatilityEstimator::TimeSampled, 60));
+        assert!((fine - coarse).abs() < 0.08, "{} vs {}", fine, coarse);
+    }
+
+    #[test]
+    fn horizon_limits_the_window() {
+        // Quiet first half, volatile second half: a short horizon only sees the latter
+        let mut ticks: Vec<(u64, f64)> = (0..3600).map(|s| (s * 1_000_000_000, 30_000.0)).collect();
+        let offset = 3600 * 1_000_000_000;
+        ticks.extend(gbm_ticks(1, 5).into_iter().map(|(t, p)| (t + offset, p)));
+        let mut cfg = config(VolatilityEstimator::TimeSampled, 10);
+        cfg.horizon = Duration::from_secs(3000);
+        assert_close(estimate(&series(&ticks), &cfg), 0.15);
+    }
+
+    #[test]
+    fn falls_back_to_default_without_history() {
+        let cfg = VolatilityConfig::default();
+        assert_eq!(estimate(&series(&[]), &cfg), cfg.default_volatility);
+        assert_eq!(estimate(&series(&[(0, 100.0), (1, 101.0)]), &cfg), cfg.default_volatility);
+    }
+}
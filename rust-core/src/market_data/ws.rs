@@ -0,0 +1,44 @@
+//! WebSocket plumbing shared by the exchange adapters.
+
+use super::source::FeedError;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+pub(super) type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+pub(super) async fn connect(url: &str) -> Result<WsStream, FeedError> {
+    let url = Url::parse(url).map_err(|_| FeedError::InvalidUrl(url.to_string()))?;
+    let (stream, _) = connect_async(url).await.map_err(|e| FeedError::Connect(e.to_string()))?;
+    Ok(stream)
+}
+
+pub(super) async fn send_json(stream: &mut WsStream, request: &Value) -> Result<(), FeedError> {
+    stream
+        .send(Message::Text(request.to_string()))
+        .await
+        .map_err(|e| FeedError::WebSocket(e.to_string()))
+}
+
+/// Next text frame from the connection in `slot`, skipping control and
+/// binary frames (pings are answered by tungstenite). The connection is
+/// dropped from `slot` when it closes or fails. Cancel-safe.
+pub(super) async fn next_text(slot: &mut Option<WsStream>) -> Result<String, FeedError> {
+    let stream = slot.as_mut().ok_or(FeedError::NotConnected)?;
+    loop {
+        match stream.next().await {
+            Some(Ok(Message::Text(text))) => return Ok(text),
+            Some(Ok(Message::Close(_))) | None => {
+                *slot = None;
+                return Err(FeedError::Disconnected);
+            }
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
+                *slot = None;
+                return Err(FeedError::WebSocket(e.to_string()));
+            }
+        }
+    }
+}
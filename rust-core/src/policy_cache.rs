@@ -0,0 +1,255 @@
+use crate::hjb::{HJBEngine, HjbParams, QuotePolicy};
+use crossbeam::channel::{self, Sender};
+use crossbeam::epoch::{self, Atomic, Owned, Shared};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Solved surfaces kept for reuse; the cache is cleared when it fills up.
+const MAX_CACHED_POLICIES: usize = 64;
+
+/// Largest inventory bound a surface is solved for, which bounds the size
+/// of each surface and the time to solve it.
+pub const MAX_POLICY_INVENTORY: i32 = 100;
+
+/// Settings shared by every surface of one cache.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolicySettings {
+    pub max_inventory: i32,
+    pub terminal_penalty: f64,
+    pub running_penalty: f64,
+    /// Time buckets over the horizon.
+    pub time_steps: usize,
+}
+
+impl Default for PolicySettings {
+    fn default() -> Self {
+        Self { max_inventory: 5, terminal_penalty: 0.0, running_penalty: 0.0, time_steps: 200 }
+    }
+}
+
+/// Identifies a policy surface. Volatility, k and A are bucketed so small
+/// moves reuse a surface: the bucket is the float's sign, exponent and top
+/// four mantissa bits, 16 buckets per doubling and about 4.4% apart. No
+/// logarithm is taken, keeping the key cheap enough to build per quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PolicyKey {
+    gamma: u64,
+    k: u64,
+    a: u64,
+    sigma: u64,
+    horizon_ms: u64,
+}
+
+impl PolicyKey {
+    /// `sigma` in price units per square-root second, `horizon` in seconds.
+    pub fn new(gamma: f64, k: f64, a: f64, sigma: f64, horizon: f64) -> Self {
+        Self { gamma: gamma.to_bits(), k: bucket(k), a: bucket(a), sigma: bucket(sigma), horizon_ms: (horizon * 1000.0) as u64 }
+    }
+
+    fn params(&self, settings: &PolicySettings) -> HjbParams {
+        HjbParams {
+            gamma: f64::from_bits(self.gamma),
+            sigma: bucket_center(self.sigma),
+            a: bucket_center(self.a),
+            k: bucket_center(self.k),
+            horizon: self.horizon_ms as f64 / 1000.0,
+            max_inventory: settings.max_inventory,
+            terminal_penalty: settings.terminal_penalty,
+            running_penalty: settings.running_penalty,
+        }
+    }
+}
+
+fn bucket(value: f64) -> u64 {
+    if value.is_finite() && value > 0.0 {
+        value.to_bits() >> 48
+    } else {
+        0
+    }
+}
+
+fn bucket_center(bucket: u64) -> f64 {
+    if bucket == 0 {
+        0.0
+    } else {
+        f64::from_bits(bucket << 48 | 1 << 47)
+    }
+}
+
+struct Entry {
+    key: PolicyKey,
+    policy: Arc<QuotePolicy>,
+}
+
+/// Pointer to the published surface, freed with the cache.
+struct Slot(Atomic<Entry>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        // No readers or builder can remain once the last reference is gone
+        unsafe {
+            let entry = self.0.swap(Shared::null(), Ordering::AcqRel, epoch::unprotected());
+            if !entry.is_null() {
+                drop(entry.into_owned());
+            }
+        }
+    }
+}
+
+/// Quote policy surfaces solved on a background thread and published with
+/// an atomic pointer swap, so lookups never wait on a solve or a lock.
+///
+/// Readers ask for the surface of their current parameters. When it is not
+/// the published one, they get `None` and the surface is requested from the
+/// builder, which solves it (or takes it from the cache of earlier solves)
+/// and swaps it in. Only the latest request is served when several queue up
+/// during a solve.
+///
+/// Dropping the cache never waits for the builder: it is told to stop and
+/// abandons any solve in progress at its next time step.
+pub struct PolicyCache {
+    current: Arc<Slot>,
+    /// Last key requested from the builder, to avoid flooding its queue.
+    requested: Mutex<Option<PolicyKey>>,
+    sender: Sender<PolicyKey>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PolicyCache {
+    pub fn new(settings: PolicySettings) -> Self {
+        let current = Arc::new(Slot(Atomic::null()));
+        let (sender, receiver) = channel::unbounded::<PolicyKey>();
+        let published = current.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = cancelled.clone();
+        std::thread::spawn(move || {
+            let engine = HJBEngine::new(settings.time_steps);
+            let mut solved: HashMap<PolicyKey, Arc<QuotePolicy>> = HashMap::new();
+            while let Ok(key) = receiver.recv() {
+                let key = receiver.try_iter().last().unwrap_or(key);
+                if solved.len() >= MAX_CACHED_POLICIES && !solved.contains_key(&key) {
+                    solved.clear();
+                }
+                let policy = match solved.get(&key) {
+                    Some(policy) => policy.clone(),
+                    None => match engine.solve_cancellable(&key.params(&settings), &stop) {
+                        Some(policy) => solved.entry(key).or_insert(Arc::new(policy)).clone(),
+                        None => return,
+                    },
+                };
+
+                let guard = epoch::pin();
+                let previous = published.0.swap(Owned::new(Entry { key, policy }), Ordering::AcqRel, &guard);
+                if !previous.is_null() {
+                    unsafe { guard.defer_destroy(previous) };
+                }
+            }
+        });
+        Self { current, requested: Mutex::new(None), sender, cancelled }
+    }
+
+    /// Apply `f` to the surface for `key` if it is published, otherwise
+    /// request it and return `None`. Never blocks.
+    pub fn with_policy<R>(&self, key: PolicyKey, f: impl FnOnce(&QuotePolicy) -> R) -> Option<R> {
+        let guard = epoch::pin();
+        let entry = unsafe { self.current.0.load(Ordering::Acquire, &guard).as_ref() };
+        match entry {
+            Some(entry) if entry.key == key => Some(f(&entry.policy)),
+            _ => {
+                self.request(key);
+                None
+            }
+        }
+    }
+
+    /// Ask the builder for the surface for `key` ahead of its first lookup.
+    /// Never blocks.
+    pub fn request(&self, key: PolicyKey) {
+        // Whoever holds the lock is already requesting
+        if let Ok(mut requested) = self.requested.try_lock() {
+            if *requested != Some(key) {
+                *requested = Some(key);
+                let _ = self.sender.send(key);
+            }
+        }
+    }
+}
+
+impl Drop for PolicyCache {
+    fn drop(&mut self) {
+        // The builder is detached; the closed channel ends it once idle
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for_policy(cache: &PolicyCache, key: PolicyKey) -> (Option<f64>, Option<f64>) {
+        let start = Instant::now();
+        loop {
+            if let Some(offsets) = cache.with_policy(key, |policy| policy.offsets(0, 1)) {
+                return offsets;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "surface never published");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn publishes_requested_surfaces_in_the_background() {
+        let settings = PolicySettings { time_steps: 20, ..PolicySettings::default() };
+        let cache = PolicyCache::new(settings);
+        let key = PolicyKey::new(0.05, 1.5, 1.0, 0.3, 600.0);
+        assert!(cache.with_policy(key, |_| ()).is_none());
+
+        let expected = HJBEngine::new(20).solve_crank_nicolson(&key.params(&settings)).offsets(0, 1);
+        assert_eq!(wait_for_policy(&cache, key), expected);
+
+        // Volatility within the same bucket reuses the surface, a new bucket does not
+        assert_eq!(PolicyKey::new(0.05, 1.5, 1.0, 0.301, 600.0), key);
+        let moved = PolicyKey::new(0.05, 1.5, 1.0, 0.4, 600.0);
+        assert!(cache.with_policy(moved, |_| ()).is_none());
+        assert_ne!(wait_for_policy(&cache, moved), expected);
+    }
+
+    #[test]
+    fn requested_surfaces_are_published_before_any_lookup() {
+        let cache = PolicyCache::new(PolicySettings { time_steps: 20, ..PolicySettings::default() });
+        let key = PolicyKey::new(0.05, 1.5, 1.0, 0.3, 600.0);
+        cache.request(key);
+
+        let start = Instant::now();
+        let published = || {
+            let guard = epoch::pin();
+            unsafe { cache.current.0.load(Ordering::Acquire, &guard).as_ref() }.map(|entry| entry.key)
+        };
+        while published() != Some(key) {
+            assert!(start.elapsed() < Duration::from_secs(10), "surface never published");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(cache.with_policy(key, |_| ()).is_some());
+    }
+
+    #[test]
+    fn dropping_the_cache_abandons_a_solve_in_progress() {
+        // Thousands of substeps per time step, far longer to solve than the test runs
+        let settings = PolicySettings { max_inventory: MAX_POLICY_INVENTORY, time_steps: 1000, ..PolicySettings::default() };
+        let cache = PolicyCache::new(settings);
+        cache.request(PolicyKey::new(0.05, 1.5, 1500.0, 0.3, 1000.0));
+        std::thread::sleep(Duration::from_millis(50));
+        let slot = Arc::downgrade(&cache.current);
+
+        let start = Instant::now();
+        drop(cache);
+        assert!(start.elapsed() < Duration::from_millis(100), "drop waited {:?}", start.elapsed());
+        // The builder releases the slot once it stops
+        while slot.upgrade().is_some() {
+            assert!(start.elapsed() < Duration::from_secs(10), "builder kept solving");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use crate::fpga_bridge::FPGAEngine;
+use crate::market_data::SECONDS_PER_YEAR;
+use crate::policy_cache::{PolicyCache, PolicyKey, PolicySettings};
+use std::time::Duration;
+
+/// Market and inventory state a quote is computed from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QuoteSnapshot {
+    /// Fair value the quotes are placed around.
+    pub mid: f64,
+    pub market_bid: f64,
+    pub market_ask: f64,
+    pub inventory: i32,
+    /// Annualized realized volatility.
+    pub volatility: f64,
+    /// Risk aversion.
+    pub gamma: f64,
+    /// Order arrival decay per unit of price distance, configured or fitted.
+    pub k: f64,
+    /// Order arrival intensity at the mid in trades per second, configured
+    /// or fitted.
+    pub a: f64,
+    /// Whether `k` and `a` were fitted to recent trades.
+    pub fitted: bool,
+    /// Time left until the session horizon (T - t) in years. 1.0 when no
+    /// horizon is configured, which gives the stationary A-S quotes.
+    pub time_to_horizon: f64,
+    /// Length of the session ending at the horizon in years, 0 when no
+    /// horizon is configured.
+    pub session_length: f64,
+}
+
+/// Bid and ask prices and sizes. A side that should not be quoted has a
+/// zero price and size.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QuotePair {
+    pub bid: f64,
+    pub ask: f64,
+    pub bid_size: f64,
+    pub ask_size: f64,
+}
+
+impl QuotePair {
+    fn symmetric(center: f64, half_spread: f64, size: f64) -> Self {
+        Self { bid: center - half_spread, ask: center + half_spread, bid_size: size, ask_size: size }
+    }
+
+    /// Drop the side whose fill would take `inventory` beyond
+    /// `±max_inventory`.
+    fn within_bounds(mut self, inventory: i32, max_inventory: i32) -> Self {
+        let (inventory, max_inventory) = (inventory as f64, max_inventory as f64);
+        if inventory + self.bid_size > max_inventory {
+            (self.bid, self.bid_size) = (0.0, 0.0);
+        }
+        if inventory - self.ask_size < -max_inventory {
+            (self.ask, self.ask_size) = (0.0, 0.0);
+        }
+        self
+    }
+}
+
+/// A trading session that closes every `length`, `close_offset` after each
+/// multiple of `length` since the Unix epoch. The default closes daily at
+/// midnight UTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionHorizon {
+    pub length: Duration,
+    pub close_offset: Duration,
+}
+
+impl Default for SessionHorizon {
+    fn default() -> Self {
+        Self { length: Duration::from_secs(86_400), close_offset: Duration::ZERO }
+    }
+}
+
+impl SessionHorizon {
+    /// Time until the next close, in `(0, length]`: a session that just
+    /// closed has the whole next one ahead of it.
+    pub fn remaining(&self, now_ns: u64) -> Duration {
+        let length_ns = (self.length.as_nanos() as u64).max(1);
+        let close_ns = self.close_offset.as_nanos() as u64 % length_ns;
+        let until_ns = (close_ns + length_ns - now_ns % length_ns) % length_ns;
+        Duration::from_nanos(if until_ns == 0 { length_ns } else { until_ns })
+    }
+
+    /// Elapsed fraction of the current session, in `[0, 1)`.
+    pub fn progress(&self, now_ns: u64) -> f64 {
+        1.0 - self.remaining(now_ns).as_secs_f64() / self.length.as_secs_f64()
+    }
+
+    /// `T - t` in years, the unit of the annualized volatility.
+    pub fn time_to_horizon(&self, now_ns: u64) -> f64 {
+        self.remaining(now_ns).as_secs_f64() / SECONDS_PER_YEAR
+    }
+}
+
+/// A quote model the core can be switched to at runtime.
+pub trait QuotingStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair;
+
+    /// Called when the configuration changes, so a model that precomputes
+    /// from its parameters can start before the next quote needs them.
+    fn prepare(&self, _snapshot: &QuoteSnapshot) {}
+}
+
+/// Avellaneda-Stoikov reservation price and spread, computed on the FPGA
+/// when one is attached.
+pub struct AvellanedaStoikov {
+    engine: FPGAEngine,
+    order_size: f64,
+}
+
+impl AvellanedaStoikov {
+    pub fn new(order_size: f64) -> Self {
+        Self { engine: FPGAEngine::new(), order_size }
+    }
+}
+
+impl QuotingStrategy for AvellanedaStoikov {
+    fn name(&self) -> &'static str {
+        "avellaneda-stoikov"
+    }
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair {
+        let (bid, ask, _latency_ns) = self.engine.calculate_optimal_quotes(
+            snapshot.mid,
+            snapshot.inventory,
+            snapshot.volatility,
+            snapshot.gamma,
+            snapshot.k,
+            snapshot.fitted.then_some(snapshot.k),
+            snapshot.time_to_horizon,
+        );
+        QuotePair { bid, ask, bid_size: self.order_size, ask_size: self.order_size }
+    }
+}
+
+/// Constant spread around the fair value, ignoring inventory and volatility.
+pub struct FixedSpread {
+    pub spread_bps: f64,
+    pub order_size: f64,
+}
+
+impl QuotingStrategy for FixedSpread {
+    fn name(&self) -> &'static str {
+        "fixed-spread"
+    }
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair {
+        let half_spread = snapshot.mid * self.spread_bps / 20_000.0;
+        QuotePair::symmetric(snapshot.mid, half_spread, self.order_size)
+    }
+}
+
+/// Symmetric spread proportional to the expected price move over `horizon`,
+/// ignoring inventory. A baseline for the inventory-aware models.
+pub struct VolatilityScaled {
+    /// Half spread in standard deviations of the move over `horizon`.
+    pub multiplier: f64,
+    pub horizon: Duration,
+    /// Floor on the full spread, so quotes stay apart in quiet markets.
+    pub min_spread_bps: f64,
+    pub order_size: f64,
+}
+
+impl VolatilityScaled {
+    pub fn new(multiplier: f64, order_size: f64) -> Self {
+        Self { multiplier, horizon: Duration::from_secs(60), min_spread_bps: 1.0, order_size }
+    }
+}
+
+impl QuotingStrategy for VolatilityScaled {
+    fn name(&self) -> &'static str {
+        "volatility-scaled"
+    }
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair {
+        let move_stddev = snapshot.volatility * (self.horizon.as_secs_f64() / SECONDS_PER_YEAR).sqrt();
+        let half_spread = snapshot.mid * (self.multiplier * move_stddev).max(self.min_spread_bps / 20_000.0);
+        QuotePair::symmetric(snapshot.mid, half_spread, self.order_size)
+    }
+}
+
+/// Guéant-Lehalle-Fernandez-Tapia asymptotic closed-form quotes with hard
+/// inventory bounds.
+///
+/// With `ω = sqrt(σ²γ / (2kA) · (1 + γ/k)^(1 + k/γ))`, the offsets from the
+/// mid at inventory `q` are
+///
+/// ```text
+/// δ_bid = ln(1 + γ/k) / γ + (2q + 1)/2 · ω
+/// δ_ask = ln(1 + γ/k) / γ − (2q − 1)/2 · ω
+/// ```
+///
+/// with `σ` the volatility in price units per square-root second. Unlike the
+/// Avellaneda-Stoikov formula the skew is bounded: inventory is clamped to
+/// `±max_inventory`, and the side whose fill would take it beyond the bound
+/// is not quoted.
+pub struct Glft {
+    pub max_inventory: i32,
+    pub order_size: f64,
+}
+
+impl Glft {
+    /// Offsets of the bid and ask below and above the mid, before the
+    /// inventory bounds are applied.
+    pub fn offsets(&self, snapshot: &QuoteSnapshot) -> (f64, f64) {
+        let QuoteSnapshot { gamma, k, a, .. } = *snapshot;
+        let sigma = snapshot.volatility * snapshot.mid / SECONDS_PER_YEAR.sqrt();
+        let q = snapshot.inventory.clamp(-self.max_inventory, self.max_inventory) as f64;
+
+        let base = (1.0 + gamma / k).ln() / gamma;
+        let omega = (sigma * sigma * gamma / (2.0 * k * a) * (1.0 + gamma / k).powf(1.0 + k / gamma)).sqrt();
+        (base + (2.0 * q + 1.0) / 2.0 * omega, base - (2.0 * q - 1.0) / 2.0 * omega)
+    }
+}
+
+impl QuotingStrategy for Glft {
+    fn name(&self) -> &'static str {
+        "glft"
+    }
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair {
+        let (bid_offset, ask_offset) = self.offsets(snapshot);
+        QuotePair {
+            bid: snapshot.mid - bid_offset,
+            ask: snapshot.mid + ask_offset,
+            bid_size: self.order_size,
+            ask_size: self.order_size,
+        }
+        .within_bounds(snapshot.inventory, self.max_inventory)
+    }
+}
+
+/// Quotes from the HJB policy surface for the current parameters: a table
+/// lookup and a linear interpolation in time. Surfaces are solved in the
+/// background as soon as the configuration changes, or when market moves
+/// take the parameters to a new key, and the GLFT closed form is quoted
+/// until the surface is ready.
+///
+/// The horizon is the configured session; without one the surface of a
+/// one-day session is read at its start, far enough from the horizon to be
+/// stationary.
+pub struct HjbPolicy {
+    cache: PolicyCache,
+    fallback: Glft,
+}
+
+impl HjbPolicy {
+    pub fn new(settings: PolicySettings, order_size: f64) -> Self {
+        let fallback = Glft { max_inventory: settings.max_inventory, order_size };
+        Self { cache: PolicyCache::new(settings), fallback }
+    }
+
+    /// Surface for the snapshot's parameters and the seconds elapsed into
+    /// its horizon.
+    fn key(snapshot: &QuoteSnapshot) -> (PolicyKey, f64) {
+        let (horizon, elapsed) = if snapshot.session_length > 0.0 {
+            let remaining = snapshot.time_to_horizon.min(snapshot.session_length);
+            (snapshot.session_length * SECONDS_PER_YEAR, (snapshot.session_length - remaining) * SECONDS_PER_YEAR)
+        } else {
+            (SessionHorizon::default().length.as_secs_f64(), 0.0)
+        };
+        let sigma = snapshot.volatility * snapshot.mid / SECONDS_PER_YEAR.sqrt();
+        (PolicyKey::new(snapshot.gamma, snapshot.k, snapshot.a, sigma, horizon), elapsed)
+    }
+}
+
+impl QuotingStrategy for HjbPolicy {
+    fn name(&self) -> &'static str {
+        "hjb-policy"
+    }
+
+    fn quote(&self, snapshot: &QuoteSnapshot) -> QuotePair {
+        let (key, elapsed) = Self::key(snapshot);
+        // A miss requests the surface, so quotes move onto it once solved
+        let offsets = self.cache.with_policy(key, |policy| policy.offsets_at(elapsed, snapshot.inventory));
+        let Some((bid_offset, ask_offset)) = offsets else {
+            return self.fallback.quote(snapshot);
+        };
+        let mut quotes = QuotePair::default();
+        if let Some(offset) = bid_offset {
+            (quotes.bid, quotes.bid_size) = (snapshot.mid - offset, self.fallback.order_size);
+        }
+        if let Some(offset) = ask_offset {
+            (quotes.ask, quotes.ask_size) = (snapshot.mid + offset, self.fallback.order_size);
+        }
+        quotes.within_bounds(snapshot.inventory, self.fallback.max_inventory)
+    }
+
+    fn prepare(&self, snapshot: &QuoteSnapshot) {
+        self.cache.request(Self::key(snapshot).0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(inventory: i32, volatility: f64) -> QuoteSnapshot {
+        QuoteSnapshot {
+            mid: 100.0,
+            market_bid: 99.99,
+            market_ask: 100.01,
+            inventory,
+            volatility,
+            gamma: 0.1,
+            k: 1.5,
+            a: 1.0,
+            fitted: false,
+            time_to_horizon: 1.0,
+            session_length: 0.0,
+        }
+    }
+
+    fn glft(max_inventory: i32) -> Glft {
+        Glft { max_inventory, order_size: 1.0 }
+    }
+
+    fn center(quotes: &QuotePair) -> f64 {
+        (quotes.bid + quotes.ask) / 2.0
+    }
+
+    #[test]
+    fn fixed_spread_centers_a_constant_spread_on_the_fair_value() {
+        let model = FixedSpread { spread_bps: 10.0, order_size: 2.5 };
+        for (inventory, volatility) in [(0, 0.6), (5, 0.6), (-5, 3.0)] {
+            let quotes = model.quote(&snapshot(inventory, volatility));
+            assert!((quotes.ask - quotes.bid - 0.1).abs() < 1e-9, "{:?}", quotes);
+            assert!((center(&quotes) - 100.0).abs() < 1e-9, "{:?}", quotes);
+            assert_eq!((quotes.bid_size, quotes.ask_size), (2.5, 2.5));
+        }
+        // The spread is relative to the fair value, not the market
+        let quotes = model.quote(&QuoteSnapshot { mid: 200.0, ..snapshot(0, 0.6) });
+        assert!((quotes.bid - 199.9).abs() < 1e-9 && (quotes.ask - 200.1).abs() < 1e-9, "{:?}", quotes);
+    }
+
+    #[test]
+    fn volatility_scaled_spread_follows_the_expected_move() {
+        let model = VolatilityScaled::new(2.0, 0.5);
+        let move_stddev = |volatility: f64| volatility * (60.0 / SECONDS_PER_YEAR).sqrt();
+        for volatility in [0.3, 0.6, 1.2] {
+            let quotes = model.quote(&snapshot(3, volatility));
+            let half_spread = 100.0 * 2.0 * move_stddev(volatility);
+            assert!((quotes.ask - quotes.bid - 2.0 * half_spread).abs() < 1e-9, "{:?}", quotes);
+            assert!((center(&quotes) - 100.0).abs() < 1e-9, "{:?}", quotes);
+            assert_eq!((quotes.bid_size, quotes.ask_size), (0.5, 0.5));
+        }
+
+        // Quiet markets fall back to the minimum spread of 1 bp
+        let quiet = model.quote(&snapshot(0, 1e-6));
+        assert!((quiet.ask - quiet.bid - 0.01).abs() < 1e-9, "{:?}", quiet);
+        assert!((center(&quiet) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn matches_avellaneda_stoikov_at_zero_inventory_without_volatility() {
+        // Both reduce to the optimal half spread ln(1 + γ/k) / γ around the mid
+        let flat = snapshot(0, 1e-9);
+        let glft_quotes = glft(10).quote(&flat);
+        let as_quotes = AvellanedaStoikov::new(1.0).quote(&flat);
+        assert!((glft_quotes.bid - as_quotes.bid).abs() < 1e-9, "{:?} vs {:?}", glft_quotes, as_quotes);
+        assert!((glft_quotes.ask - as_quotes.ask).abs() < 1e-9, "{:?} vs {:?}", glft_quotes, as_quotes);
+    }
+
+    #[test]
+    fn skews_like_avellaneda_stoikov_at_small_inventory() {
+        let model = glft(10);
+        let avellaneda_stoikov = AvellanedaStoikov::new(1.0);
+        // Hand-computed for γ = 0.1, k = 1.5, A = 1 and 60% volatility at a
+        // mid of 100: ln(1 + γ/k)/γ = 0.645385 and ω = 0.003269
+        let (base, omega) = (0.645_385_211_375_711_6, 0.003_269_016_467_773_915);
+        for inventory in -3..=3 {
+            let glft_quotes = model.quote(&snapshot(inventory, 0.6));
+            let as_quotes = avellaneda_stoikov.quote(&snapshot(inventory, 0.6));
+            // The center moves by −q·ω, with the same sign as the A-S skew
+            let glft_skew = center(&glft_quotes) - 100.0;
+            let as_skew = center(&as_quotes) - 100.0;
+            assert!((glft_skew + inventory as f64 * omega).abs() < 1e-9, "inventory {}: {:?}", inventory, glft_quotes);
+            assert_eq!(glft_skew.total_cmp(&0.0), as_skew.total_cmp(&0.0), "inventory {}", inventory);
+            // And, as in A-S, the half spread does not depend on inventory
+            let half_spread = (glft_quotes.ask - glft_quotes.bid) / 2.0;
+            assert!((half_spread - (base + omega / 2.0)).abs() < 1e-9, "inventory {}: {:?}", inventory, glft_quotes);
+        }
+    }
+
+    #[test]
+    fn stops_quoting_the_side_that_would_breach_the_bound() {
+        let model = glft(3);
+        let inside = model.quote(&snapshot(2, 0.6));
+        assert!(inside.bid > 0.0 && inside.ask > 0.0);
+        assert_eq!((inside.bid_size, inside.ask_size), (1.0, 1.0));
+
+        let long = model.quote(&snapshot(3, 0.6));
+        assert_eq!((long.bid, long.bid_size), (0.0, 0.0));
+        assert!(long.ask > 0.0 && long.ask_size == 1.0);
+
+        let short = model.quote(&snapshot(-3, 0.6));
+        assert_eq!((short.ask, short.ask_size), (0.0, 0.0));
+        assert!(short.bid > 0.0 && short.bid_size == 1.0);
+    }
+
+    #[test]
+    fn skew_stays_bounded_beyond_the_limit() {
+        let model = glft(5);
+        let at_limit = model.quote(&snapshot(5, 0.6));
+        let beyond = model.quote(&snapshot(50, 0.6));
+        assert_eq!(beyond.ask, at_limit.ask);
+
+        // A-S keeps moving its quotes with inventory
+        let avellaneda_stoikov = AvellanedaStoikov::new(1.0);
+        assert!(avellaneda_stoikov.quote(&snapshot(50, 0.6)).ask < avellaneda_stoikov.quote(&snapshot(5, 0.6)).ask);
+    }
+
+    #[test]
+    fn avellaneda_stoikov_skew_and_spread_shrink_toward_the_horizon() {
+        let model = AvellanedaStoikov::new(1.0);
+        let optimal_half_spread = (1.0f64 + 0.1 / 1.5).ln() / 0.1;
+        let stationary = model.quote(&snapshot(4, 0.6));
+        let mut previous = stationary;
+        for time_to_horizon in [0.5, 0.1, 1e-12] {
+            let quotes = model.quote(&QuoteSnapshot { time_to_horizon, ..snapshot(4, 0.6) });
+            assert!(center(&quotes) > center(&previous) && center(&quotes) < 100.0);
+            assert!(quotes.ask - quotes.bid < previous.ask - previous.bid);
+            previous = quotes;
+        }
+        // At the close only the optimal spread around the mid remains
+        assert!((center(&previous) - 100.0).abs() < 1e-9);
+        assert!((previous.ask - previous.bid - 2.0 * optimal_half_spread).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hjb_policy_quotes_glft_until_its_surface_is_ready() {
+        let settings = PolicySettings { time_steps: 20, ..PolicySettings::default() };
+        let model = HjbPolicy::new(settings, 2.0);
+        let quoted = snapshot(1, 0.6);
+        let closed_form = Glft { max_inventory: settings.max_inventory, order_size: 2.0 }.quote(&quoted);
+        // The first quote only requests the surface
+        assert_eq!(model.quote(&quoted), closed_form);
+
+        let (key, elapsed) = HjbPolicy::key(&quoted);
+        let start = std::time::Instant::now();
+        let (bid_offset, ask_offset) = loop {
+            if let Some(offsets) = model.cache.with_policy(key, |policy| policy.offsets_at(elapsed, 1)) {
+                break offsets;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "surface never published");
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        let quotes = model.quote(&quoted);
+        assert_eq!((quotes.bid, quotes.ask), (100.0 - bid_offset.unwrap(), 100.0 + ask_offset.unwrap()));
+        assert_eq!((quotes.bid_size, quotes.ask_size), (2.0, 2.0));
+        assert_ne!(quotes, closed_form);
+
+        // New parameters fall back again until their surface is solved
+        let moved = QuoteSnapshot { gamma: 0.2, ..quoted };
+        assert_eq!(model.quote(&moved), Glft { max_inventory: settings.max_inventory, order_size: 2.0 }.quote(&moved));
+    }
+
+    #[test]
+    fn hjb_policy_follows_the_market_after_prepare() {
+        let settings = PolicySettings { time_steps: 20, ..PolicySettings::default() };
+        let model = HjbPolicy::new(settings, 1.0);
+        let fallback = Glft { max_inventory: settings.max_inventory, order_size: 1.0 };
+        // Quotes leave the closed form once the snapshot's surface is solved
+        let wait_for_surface = |snapshot: &QuoteSnapshot| {
+            let start = std::time::Instant::now();
+            while model.quote(snapshot) == fallback.quote(snapshot) {
+                assert!(start.elapsed() < Duration::from_secs(10), "surface never used for {:?}", snapshot);
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+
+        // Prepared before any market data, as on startup
+        model.prepare(&QuoteSnapshot { mid: 0.0, volatility: 0.0, ..snapshot(1, 0.6) });
+        wait_for_surface(&snapshot(1, 0.6));
+        // Volatility and the mid drifting into new buckets
+        wait_for_surface(&snapshot(1, 1.2));
+        wait_for_surface(&QuoteSnapshot { mid: 150.0, ..snapshot(1, 1.2) });
+    }
+
+    #[test]
+    fn session_horizon_counts_down_to_the_next_close() {
+        let hour = 3_600_000_000_000u64;
+        let session = SessionHorizon { length: Duration::from_secs(8 * 3600), close_offset: Duration::from_secs(3600) };
+        assert_eq!(session.remaining(0), Duration::from_secs(3600));
+        assert_eq!(session.remaining(hour), Duration::from_secs(8 * 3600));
+        assert_eq!(session.remaining(3 * hour), Duration::from_secs(6 * 3600));
+        assert!((session.progress(5 * hour) - 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,251 @@
+mod common;
+
+use common::{wait_for, Scenario, StandInExchange};
+use std::time::Duration;
+use serde_json::Value;
+use tradechest_core::market_data::{BinanceSource, FeedManager, FeedStatus, MarketDataFeed, StreamKind};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const BIDS: [(f64, f64); 2] = [(100.0, 1.0), (99.5, 2.0)];
+const ASKS: [(f64, f64); 2] = [(100.5, 1.0), (101.0, 3.0)];
+
+fn start_feed(exchange: &StandInExchange) -> MarketDataFeed {
+    let mut feed = MarketDataFeed::new("BTCUSDT".to_string());
+    feed.set_base_url(&exchange.url());
+    feed.start();
+    feed
+}
+
+fn start_manager(exchange: &StandInExchange) -> FeedManager {
+    FeedManager::new(Box::new(BinanceSource::with_base_url(&exchange.url())))
+}
+
+/// `SUBSCRIBE` or `UNSUBSCRIBE` requests the exchange received, as their
+/// stream names.
+fn requested_streams(exchange: &StandInExchange, method: &str) -> Vec<Value> {
+    exchange.requests().into_iter().filter(|request| request["method"] == method).map(|request| request["params"].clone()).collect()
+}
+
+#[tokio::test]
+async fn streams_ticker_depth_and_trades() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .snapshot(100, &BIDS, &ASKS)
+            .ticker("BTCUSDT", 100.25)
+            .depth_update("BTCUSDT", 101, 102, &[(100.1, 0.5)], &[])
+            .trade("BTCUSDT", 100.3, 0.4, false)
+            .trade("BTCUSDT", 100.2, 0.1, true),
+    )
+    .await;
+    let feed = start_feed(&exchange);
+
+    wait_for("two trades", TIMEOUT, || feed.trade_flow().trade_count == 2).await;
+    wait_for("book sync", TIMEOUT, || feed.order_book().last_update_id() == Some(102)).await;
+
+    let connections = exchange.connections();
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0], "/stream?streams=btcusdt@ticker/btcusdt@depth@100ms/btcusdt@aggTrade");
+    assert_eq!(exchange.snapshot_requests(), 1);
+
+    assert_eq!(feed.current_price(), 100.25);
+    let top = feed.top_of_book();
+    assert_eq!((top.bid, top.bid_size), (100.1, 0.5));
+    assert_eq!((top.ask, top.ask_size), (100.5, 1.0));
+
+    let flow = feed.trade_flow();
+    assert_eq!(flow.buy_volume, 0.4);
+    assert_eq!(flow.sell_volume, 0.1);
+    assert_eq!(feed.health().status(), FeedStatus::Live);
+
+    // Every scripted event carries a Binance event time in milliseconds
+    assert_eq!(feed.latency(StreamKind::Ticker).sample_count, 1);
+    assert_eq!(feed.latency(StreamKind::Trade).sample_count, 2);
+    assert_eq!(feed.latency(StreamKind::Book).sample_count, 1);
+    let offset_ns = feed.clock_offset_ns().unwrap();
+    assert!(offset_ns.abs() < 1_000_000_000, "offset {}ns", offset_ns);
+}
+
+#[tokio::test]
+async fn skips_malformed_messages_without_reconnecting() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .raw("{\"stream\":\"btcusdt@ticker\",")
+            .raw("{\"stream\":\"btcusdt@ticker\",\"data\":{\"c\":\"not a price\"}}")
+            .raw("{\"result\":null,\"id\":1}")
+            .raw("[]")
+            .ticker("BTCUSDT", 101.5),
+    )
+    .await;
+    let feed = start_feed(&exchange);
+
+    wait_for("ticker", TIMEOUT, || feed.current_price() == 101.5).await;
+    assert_eq!(exchange.connections().len(), 1);
+    let health = feed.health().snapshot();
+    assert_eq!(health.error_count, 0);
+    assert_eq!(health.reconnect_count, 0);
+}
+
+#[tokio::test]
+async fn resyncs_book_after_out_of_order_updates() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .snapshot(100, &BIDS, &ASKS)
+            .snapshot(200, &BIDS, &[(100.4, 2.0)])
+            // Arrives before the snapshot and is replayed onto it
+            .depth_update("BTCUSDT", 101, 102, &[(100.1, 0.5)], &[])
+            // Already covered by the snapshot
+            .depth_update("BTCUSDT", 95, 100, &[(50.0, 9.0)], &[])
+            .pause(Duration::from_millis(1500))
+            // Older than the current book
+            .depth_update("BTCUSDT", 99, 101, &[(50.0, 9.0)], &[])
+            // Skips 103..=149: the book must be rebuilt from a new snapshot
+            .depth_update("BTCUSDT", 150, 151, &[(50.0, 9.0)], &[])
+            .depth_update("BTCUSDT", 201, 202, &[], &[(100.3, 0.7)]),
+    )
+    .await;
+    let feed = start_feed(&exchange);
+
+    wait_for("initial sync", TIMEOUT, || feed.order_book().last_update_id() == Some(102)).await;
+    assert_eq!(feed.current_bid(), 100.1);
+    assert_eq!(feed.order_book().bids().find(|&(price, _)| price == 50.0), None);
+
+    wait_for("resync", TIMEOUT, || feed.order_book().last_update_id() == Some(202)).await;
+    let book = feed.order_book();
+    assert_eq!(book.gap_count(), 1);
+    assert_eq!(book.best_bid(), Some((100.0, 1.0)));
+    assert_eq!(book.best_ask(), Some((100.3, 0.7)));
+    assert_eq!(book.bids().find(|&(price, _)| price == 50.0), None);
+    assert_eq!(exchange.snapshot_requests(), 2);
+}
+
+#[tokio::test]
+async fn reconnects_after_disconnect() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .snapshot(100, &BIDS, &ASKS)
+            .ticker("BTCUSDT", 100.0)
+            .depth_update("BTCUSDT", 101, 101, &[(100.1, 0.5)], &[])
+            .pause(Duration::from_millis(100))
+            .disconnect()
+            .ticker("BTCUSDT", 102.0),
+    )
+    .await;
+    let feed = start_feed(&exchange);
+
+    wait_for("initial sync", TIMEOUT, || feed.order_book().last_update_id() == Some(101)).await;
+    wait_for("ticker after reconnect", TIMEOUT, || feed.current_price() == 102.0).await;
+
+    assert_eq!(exchange.connections().len(), 2);
+    assert!(exchange.finished());
+    let health = feed.health().snapshot();
+    assert_eq!(health.status, FeedStatus::Live);
+    assert_eq!(health.reconnect_count, 1);
+    assert_eq!(health.error_count, 1);
+    assert!(feed.health().last_error().is_some());
+    // Deltas from the old connection cannot be bridged to the new one
+    assert!(!feed.order_book().is_synced());
+}
+
+#[tokio::test]
+async fn multiplexes_symbols_over_one_connection() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .pause(Duration::from_millis(500))
+            .ticker("BTCUSDT", 100.25)
+            .ticker("ETHUSDT", 3000.5)
+            .trade("ETHUSDT", 3000.0, 2.0, false),
+    )
+    .await;
+    let manager = start_manager(&exchange);
+    let btc = manager.subscribe("BTCUSDT");
+    let eth = manager.subscribe("ethusdt");
+
+    wait_for("ETH trade", TIMEOUT, || eth.trade_flow().trade_count == 1).await;
+    wait_for("BTC ticker", TIMEOUT, || btc.current_price() == 100.25).await;
+    assert_eq!(exchange.connections().len(), 1);
+    // Each event only reaches its own symbol
+    assert_eq!(eth.current_price(), 3000.5);
+    assert_eq!(btc.trade_flow().trade_count, 0);
+
+    let mut symbols = manager.symbols();
+    symbols.sort();
+    assert_eq!(symbols, ["BTCUSDT", "ETHUSDT"]);
+    assert_eq!(manager.feed("ETHUSDT").unwrap().current_price(), 3000.5);
+}
+
+#[tokio::test]
+async fn subscribes_on_the_live_connection() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .ticker("BTCUSDT", 100.0)
+            .pause(Duration::from_millis(1000))
+            .ticker("ETHUSDT", 3000.0),
+    )
+    .await;
+    let manager = start_manager(&exchange);
+    let btc = manager.subscribe("BTCUSDT");
+    wait_for("BTC ticker", TIMEOUT, || btc.current_price() == 100.0).await;
+
+    let eth = manager.subscribe("ETHUSDT");
+    wait_for("ETH ticker", TIMEOUT, || eth.current_price() == 3000.0).await;
+
+    let connections = exchange.connections();
+    assert_eq!(connections, ["/stream?streams=btcusdt@ticker/btcusdt@depth@100ms/btcusdt@aggTrade"]);
+    let subscribed = requested_streams(&exchange, "SUBSCRIBE");
+    assert_eq!(subscribed.len(), 1);
+    assert_eq!(subscribed[0], serde_json::json!(["ethusdt@ticker", "ethusdt@depth@100ms", "ethusdt@aggTrade"]));
+    assert_eq!(eth.health().status(), FeedStatus::Live);
+}
+
+#[tokio::test]
+async fn routes_every_subscriber_until_the_last_leaves() {
+    let exchange = StandInExchange::start(
+        Scenario::new()
+            .pause(Duration::from_millis(500))
+            .ticker("BTCUSDT", 100.0)
+            .pause(Duration::from_millis(1000))
+            .ticker("BTCUSDT", 101.0),
+    )
+    .await;
+    let manager = start_manager(&exchange);
+    let mut first = MarketDataFeed::new("BTCUSDT".to_string());
+    let mut second = MarketDataFeed::new("BTCUSDT".to_string());
+    first.start_on(&manager);
+    second.start_on(&manager);
+
+    wait_for("first ticker", TIMEOUT, || first.current_price() == 100.0).await;
+    wait_for("second ticker", TIMEOUT, || second.current_price() == 100.0).await;
+
+    // The remaining subscriber keeps the symbol on the connection
+    drop(first);
+    wait_for("ticker after the first left", TIMEOUT, || second.current_price() == 101.0).await;
+    assert!(requested_streams(&exchange, "UNSUBSCRIBE").is_empty());
+
+    drop(second);
+    wait_for("unsubscribe", TIMEOUT, || !requested_streams(&exchange, "UNSUBSCRIBE").is_empty()).await;
+    assert_eq!(
+        requested_streams(&exchange, "UNSUBSCRIBE"),
+        [serde_json::json!(["btcusdt@ticker", "btcusdt@depth@100ms", "btcusdt@aggTrade"])]
+    );
+    assert!(requested_streams(&exchange, "SUBSCRIBE").is_empty());
+    assert_eq!(exchange.connections().len(), 1);
+}
+
+#[tokio::test]
+async fn shares_one_feed_per_symbol_until_every_view_is_dropped() {
+    let exchange = StandInExchange::start(Scenario::new().pause(Duration::from_millis(500)).ticker("BTCUSDT", 100.0)).await;
+    let manager = start_manager(&exchange);
+    let first = manager.subscribe("BTCUSDT");
+    let second = manager.subscribe("btcusdt");
+
+    wait_for("ticker", TIMEOUT, || first.current_price() == 100.0).await;
+    assert_eq!(second.current_price(), 100.0);
+
+    drop(first);
+    assert_eq!(manager.symbols(), ["BTCUSDT"]);
+    drop(second);
+    assert!(manager.symbols().is_empty());
+    assert!(manager.feed("BTCUSDT").is_none());
+    wait_for("unsubscribe", TIMEOUT, || !requested_streams(&exchange, "UNSUBSCRIBE").is_empty()).await;
+}
@@ -0,0 +1,275 @@
+//! In-process stand-in for the Binance spot endpoints used by the feed.
+//!
+//! The server plays a `Scenario` out over its WebSocket connections: text
+//! frames in Binance combined-stream format, raw (possibly malformed)
+//! frames, pauses and disconnects. Steps continue where they left off on
+//! the next connection, so reconnect behaviour can be scripted end to end.
+//! Plain HTTP requests on the same port are answered as
+//! `GET /api/v3/depth` with the queued order book snapshots, and requests
+//! the client sends over WebSocket are kept for inspection.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Send one text frame as is.
+    Send(String),
+    Pause(Duration),
+    /// Drop the TCP connection without a close frame.
+    Disconnect,
+}
+
+/// Script of WebSocket steps plus the depth snapshots served over REST,
+/// built with Binance's message formats.
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    steps: Vec<Step>,
+    snapshots: Vec<Value>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `<symbol>@ticker` event with last price `price`.
+    pub fn ticker(self, symbol: &str, price: f64) -> Self {
+        let data = json!({ "e": "24hrTicker", "E": event_time_ms(), "s": symbol.to_uppercase(), "c": price.to_string() });
+        self.stream(&format!("{}@ticker", symbol.to_lowercase()), data)
+    }
+
+    /// `<symbol>@aggTrade` event; `buyer_maker` means the seller aggressed.
+    pub fn trade(self, symbol: &str, price: f64, quantity: f64, buyer_maker: bool) -> Self {
+        let data = json!({
+            "e": "aggTrade",
+            "E": event_time_ms(),
+            "s": symbol.to_uppercase(),
+            "p": price.to_string(),
+            "q": quantity.to_string(),
+            "m": buyer_maker,
+        });
+        self.stream(&format!("{}@aggTrade", symbol.to_lowercase()), data)
+    }
+
+    /// `<symbol>@depth@100ms` diff covering update ids `first..=last`.
+    pub fn depth_update(self, symbol: &str, first: u64, last: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Self {
+        let data = json!({
+            "e": "depthUpdate",
+            "E": event_time_ms(),
+            "s": symbol.to_uppercase(),
+            "U": first,
+            "u": last,
+            "b": levels(bids),
+            "a": levels(asks),
+        });
+        self.stream(&format!("{}@depth@100ms", symbol.to_lowercase()), data)
+    }
+
+    /// Queue a `/api/v3/depth` response. Snapshots are served in order; the
+    /// last one is repeated once the queue runs out.
+    pub fn snapshot(mut self, last_update_id: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Self {
+        self.snapshots.push(json!({ "lastUpdateId": last_update_id, "bids": levels(bids), "asks": levels(asks) }));
+        self
+    }
+
+    pub fn raw(mut self, text: &str) -> Self {
+        self.steps.push(Step::Send(text.to_string()));
+        self
+    }
+
+    pub fn pause(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Pause(duration));
+        self
+    }
+
+    pub fn disconnect(mut self) -> Self {
+        self.steps.push(Step::Disconnect);
+        self
+    }
+
+    fn stream(self, stream: &str, data: Value) -> Self {
+        self.raw(&json!({ "stream": stream, "data": data }).to_string())
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    steps: VecDeque<Step>,
+    snapshots: VecDeque<Value>,
+    /// Request paths of accepted WebSocket connections.
+    connections: Vec<String>,
+    /// JSON messages received from clients, such as `SUBSCRIBE` requests.
+    requests: Vec<Value>,
+    snapshot_requests: usize,
+}
+
+/// Running stand-in exchange; stops when dropped.
+pub struct StandInExchange {
+    addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+    task: JoinHandle<()>,
+}
+
+impl StandInExchange {
+    pub async fn start(scenario: Scenario) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shared = Arc::new(Mutex::new(Shared {
+            steps: scenario.steps.into(),
+            snapshots: scenario.snapshots.into(),
+            ..Shared::default()
+        }));
+
+        let accept_shared = shared.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, accept_shared.clone()));
+            }
+        });
+        Self { addr, shared, task }
+    }
+
+    /// Base URL to hand to `MarketDataFeed::set_base_url`.
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Request paths of the WebSocket connections accepted so far.
+    pub fn connections(&self) -> Vec<String> {
+        self.shared.lock().unwrap().connections.clone()
+    }
+
+    /// JSON messages received from clients so far, in order.
+    pub fn requests(&self) -> Vec<Value> {
+        self.shared.lock().unwrap().requests.clone()
+    }
+
+    pub fn snapshot_requests(&self) -> usize {
+        self.shared.lock().unwrap().snapshot_requests
+    }
+
+    /// Whether every scripted step has been played.
+    pub fn finished(&self) -> bool {
+        self.shared.lock().unwrap().steps.is_empty()
+    }
+}
+
+impl Drop for StandInExchange {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(stream: TcpStream, shared: Arc<Mutex<Shared>>) {
+    // Peek until the request head is complete, leaving it for the handshake
+    let mut head = [0u8; 2048];
+    let head = loop {
+        let peeked = match stream.peek(&mut head).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        let text = String::from_utf8_lossy(&head[..peeked]).into_owned();
+        if text.contains("\r\n\r\n") || peeked == head.len() {
+            break text;
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    };
+    if head.to_lowercase().contains("upgrade: websocket") {
+        // Request line: `GET <path> HTTP/1.1`
+        let path = head.split_whitespace().nth(1).unwrap_or_default().to_string();
+        serve_websocket(stream, path, shared).await;
+    } else {
+        serve_snapshot(stream, shared).await;
+    }
+}
+
+async fn serve_websocket(stream: TcpStream, path: String, shared: Arc<Mutex<Shared>>) {
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+    shared.lock().unwrap().connections.push(path);
+
+    let (mut sink, mut incoming) = ws.split();
+    let reader_shared = shared.clone();
+    let reader = tokio::spawn(async move {
+        while let Some(Ok(message)) = incoming.next().await {
+            if let Message::Text(text) = message {
+                if let Ok(request) = serde_json::from_str(&text) {
+                    reader_shared.lock().unwrap().requests.push(request);
+                }
+            }
+        }
+    });
+
+    loop {
+        let step = shared.lock().unwrap().steps.pop_front();
+        match step {
+            Some(Step::Send(text)) => {
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            Some(Step::Pause(duration)) => tokio::time::sleep(duration).await,
+            Some(Step::Disconnect) => {
+                reader.abort();
+                return;
+            }
+            None => break,
+        }
+    }
+    // Script exhausted: hold the connection open until the client leaves
+    let _ = reader.await;
+}
+
+async fn serve_snapshot(mut stream: TcpStream, shared: Arc<Mutex<Shared>>) {
+    let mut request = [0u8; 2048];
+    if stream.read(&mut request).await.is_err() {
+        return;
+    }
+    let body = {
+        let mut shared = shared.lock().unwrap();
+        shared.snapshot_requests += 1;
+        let body = if shared.snapshots.len() > 1 { shared.snapshots.pop_front() } else { shared.snapshots.front().cloned() };
+        body.map(|body| body.to_string())
+    };
+    let response = match body {
+        Some(body) => format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+        None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Binance encodes prices and quantities as strings.
+fn levels(levels: &[(f64, f64)]) -> Value {
+    levels.iter().map(|(price, quantity)| json!([price.to_string(), quantity.to_string()])).collect()
+}
+
+fn event_time_ms() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+/// Poll `condition` every 10ms until it holds, failing after `timeout`.
+pub async fn wait_for(what: &str, timeout: Duration, mut condition: impl FnMut() -> bool) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !condition() {
+        if tokio::time::Instant::now() > deadline {
+            panic!("timed out after {:?} waiting for {}", timeout, what);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}